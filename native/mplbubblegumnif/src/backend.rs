@@ -0,0 +1,302 @@
+use solana_client::{
+    client_error::Result as ClientResult,
+    rpc_client::RpcClient,
+    rpc_config::{RpcSendTransactionConfig, RpcSimulateTransactionConfig},
+    rpc_response::RpcSimulateTransactionResult,
+};
+use solana_sdk::{
+    account::Account, commitment_config::CommitmentConfig, hash::Hash, pubkey::Pubkey,
+    signature::Signature, transaction::VersionedTransaction,
+};
+use solana_transaction_status::TransactionStatus;
+
+/// The RPC calls made while building, submitting and confirming a transaction.
+///
+/// Implemented for `RpcClient`; tests pass a `MockRpc` instead so the submit path runs
+/// deterministically without a network. Legacy transactions go through the versioned
+/// methods too, since both serialize the same on the wire.
+///
+/// There is no send-and-confirm call: a send followed by `get_signature_statuses`
+/// polls is how confirmation is waited for, with the caller's timeout.
+// `ClientError` is large, but the methods return it as `RpcClient` does
+#[allow(clippy::result_large_err)]
+pub trait RpcBackend {
+    /// Endpoint URL, used to pick the explorer cluster
+    fn url(&self) -> String;
+
+    /// Commitment used for reads and when waiting for confirmation
+    fn commitment(&self) -> CommitmentConfig;
+
+    fn get_latest_blockhash(&self) -> ClientResult<Hash>;
+
+    /// Whether `blockhash` can still land a transaction, at `commitment()`
+    fn is_blockhash_valid(&self, blockhash: &Hash) -> ClientResult<bool>;
+
+    /// Fails with an `AccountNotFound` RPC error if there is no account at `pubkey`
+    fn get_account(&self, pubkey: &Pubkey) -> ClientResult<Account>;
+
+    /// The account at `pubkey`, or `None` if there is none, at `commitment()`
+    fn get_optional_account(&self, pubkey: &Pubkey) -> ClientResult<Option<Account>>;
+
+    fn get_account_data(&self, pubkey: &Pubkey) -> ClientResult<Vec<u8>> {
+        self.get_account(pubkey).map(|account| account.data)
+    }
+
+    fn get_balance(&self, pubkey: &Pubkey) -> ClientResult<u64>;
+
+    fn send_transaction_with_config(
+        &self,
+        tx: &VersionedTransaction,
+        config: RpcSendTransactionConfig,
+    ) -> ClientResult<Signature>;
+
+    fn simulate_transaction_with_config(
+        &self,
+        tx: &VersionedTransaction,
+        config: RpcSimulateTransactionConfig,
+    ) -> ClientResult<RpcSimulateTransactionResult>;
+
+    fn get_signature_statuses(
+        &self,
+        signatures: &[Signature],
+    ) -> ClientResult<Vec<Option<TransactionStatus>>>;
+
+    /// Same as `get_signature_statuses`, also searching the node's transaction history
+    fn get_signature_statuses_with_history(
+        &self,
+        signatures: &[Signature],
+    ) -> ClientResult<Vec<Option<TransactionStatus>>>;
+}
+
+impl RpcBackend for RpcClient {
+    fn url(&self) -> String {
+        RpcClient::url(self)
+    }
+
+    fn commitment(&self) -> CommitmentConfig {
+        RpcClient::commitment(self)
+    }
+
+    fn get_latest_blockhash(&self) -> ClientResult<Hash> {
+        RpcClient::get_latest_blockhash(self)
+    }
+
+    fn is_blockhash_valid(&self, blockhash: &Hash) -> ClientResult<bool> {
+        RpcClient::is_blockhash_valid(self, blockhash, RpcClient::commitment(self))
+    }
+
+    fn get_account(&self, pubkey: &Pubkey) -> ClientResult<Account> {
+        RpcClient::get_account(self, pubkey)
+    }
+
+    fn get_optional_account(&self, pubkey: &Pubkey) -> ClientResult<Option<Account>> {
+        RpcClient::get_account_with_commitment(self, pubkey, RpcClient::commitment(self))
+            .map(|response| response.value)
+    }
+
+    fn get_account_data(&self, pubkey: &Pubkey) -> ClientResult<Vec<u8>> {
+        RpcClient::get_account_data(self, pubkey)
+    }
+
+    fn get_balance(&self, pubkey: &Pubkey) -> ClientResult<u64> {
+        RpcClient::get_balance(self, pubkey)
+    }
+
+    fn send_transaction_with_config(
+        &self,
+        tx: &VersionedTransaction,
+        config: RpcSendTransactionConfig,
+    ) -> ClientResult<Signature> {
+        RpcClient::send_transaction_with_config(self, tx, config)
+    }
+
+    fn simulate_transaction_with_config(
+        &self,
+        tx: &VersionedTransaction,
+        config: RpcSimulateTransactionConfig,
+    ) -> ClientResult<RpcSimulateTransactionResult> {
+        RpcClient::simulate_transaction_with_config(self, tx, config).map(|response| response.value)
+    }
+
+    fn get_signature_statuses(
+        &self,
+        signatures: &[Signature],
+    ) -> ClientResult<Vec<Option<TransactionStatus>>> {
+        RpcClient::get_signature_statuses(self, signatures).map(|response| response.value)
+    }
+
+    fn get_signature_statuses_with_history(
+        &self,
+        signatures: &[Signature],
+    ) -> ClientResult<Vec<Option<TransactionStatus>>> {
+        RpcClient::get_signature_statuses_with_history(self, signatures)
+            .map(|response| response.value)
+    }
+}
+
+#[cfg(test)]
+pub use mock::MockRpc;
+
+#[cfg(test)]
+mod mock {
+    use super::*;
+    use solana_client::client_error::{ClientError, ClientErrorKind};
+    use solana_client::rpc_request::RpcError;
+    use solana_transaction_status::TransactionConfirmationStatus;
+    use std::cell::{Cell, RefCell};
+    use std::collections::HashMap;
+
+    /// An in-process backend with a fixed blockhash that records what is sent.
+    ///
+    /// Sent transactions confirm at `slot` on the next status poll unless `confirms` is
+    /// unset; with `lands_late` they still show up in a history search, as if they
+    /// landed after the last poll. `send_error` makes every send fail with that message
+    /// instead. The first
+    /// `unavailable_sends` sends fail as if the endpoint refused the connection. The
+    /// config of every send attempt is kept in `send_configs`. Reads
+    /// are served from `accounts`; a blockhash stays valid while `blockhash_valid` is set.
+    pub struct MockRpc {
+        pub url: String,
+        pub blockhash: Hash,
+        pub blockhash_valid: bool,
+        pub accounts: HashMap<Pubkey, Account>,
+        pub slot: u64,
+        pub confirms: bool,
+        pub lands_late: bool,
+        pub units_consumed: Option<u64>,
+        pub send_error: Option<String>,
+        pub unavailable_sends: Cell<u32>,
+        pub sent: RefCell<Vec<VersionedTransaction>>,
+        pub send_configs: RefCell<Vec<RpcSendTransactionConfig>>,
+    }
+
+    impl Default for MockRpc {
+        fn default() -> Self {
+            MockRpc {
+                url: "https://api.devnet.solana.com".to_string(),
+                blockhash: Hash::new_unique(),
+                blockhash_valid: true,
+                accounts: HashMap::new(),
+                slot: 42,
+                confirms: true,
+                lands_late: false,
+                units_consumed: Some(10_000),
+                send_error: None,
+                unavailable_sends: Cell::new(0),
+                sent: RefCell::new(Vec::new()),
+                send_configs: RefCell::new(Vec::new()),
+            }
+        }
+    }
+
+    impl RpcBackend for MockRpc {
+        fn url(&self) -> String {
+            self.url.clone()
+        }
+
+        fn commitment(&self) -> CommitmentConfig {
+            CommitmentConfig::confirmed()
+        }
+
+        fn get_latest_blockhash(&self) -> ClientResult<Hash> {
+            Ok(self.blockhash)
+        }
+
+        fn is_blockhash_valid(&self, _blockhash: &Hash) -> ClientResult<bool> {
+            Ok(self.blockhash_valid)
+        }
+
+        fn get_account(&self, pubkey: &Pubkey) -> ClientResult<Account> {
+            self.accounts.get(pubkey).cloned().ok_or_else(|| {
+                ClientError::from(RpcError::ForUser(format!(
+                    "AccountNotFound: pubkey={}",
+                    pubkey
+                )))
+            })
+        }
+
+        fn get_optional_account(&self, pubkey: &Pubkey) -> ClientResult<Option<Account>> {
+            Ok(self.accounts.get(pubkey).cloned())
+        }
+
+        fn get_balance(&self, pubkey: &Pubkey) -> ClientResult<u64> {
+            Ok(self
+                .accounts
+                .get(pubkey)
+                .map_or(0, |account| account.lamports))
+        }
+
+        fn send_transaction_with_config(
+            &self,
+            tx: &VersionedTransaction,
+            config: RpcSendTransactionConfig,
+        ) -> ClientResult<Signature> {
+            self.send_configs.borrow_mut().push(config);
+            if self.unavailable_sends.get() > 0 {
+                self.unavailable_sends.set(self.unavailable_sends.get() - 1);
+                return Err(ClientError::from(std::io::Error::new(
+                    std::io::ErrorKind::ConnectionRefused,
+                    "connection refused",
+                )));
+            }
+            if let Some(message) = &self.send_error {
+                return Err(ClientError::from(ClientErrorKind::Custom(message.clone())));
+            }
+            self.sent.borrow_mut().push(tx.clone());
+            Ok(tx.signatures[0])
+        }
+
+        fn simulate_transaction_with_config(
+            &self,
+            _tx: &VersionedTransaction,
+            _config: RpcSimulateTransactionConfig,
+        ) -> ClientResult<RpcSimulateTransactionResult> {
+            Ok(RpcSimulateTransactionResult {
+                err: None,
+                logs: Some(Vec::new()),
+                accounts: None,
+                units_consumed: self.units_consumed,
+                return_data: None,
+                inner_instructions: None,
+            })
+        }
+
+        fn get_signature_statuses(
+            &self,
+            signatures: &[Signature],
+        ) -> ClientResult<Vec<Option<TransactionStatus>>> {
+            Ok(self.statuses(signatures, self.confirms))
+        }
+
+        fn get_signature_statuses_with_history(
+            &self,
+            signatures: &[Signature],
+        ) -> ClientResult<Vec<Option<TransactionStatus>>> {
+            Ok(self.statuses(signatures, self.confirms || self.lands_late))
+        }
+    }
+
+    impl MockRpc {
+        /// Statuses of `signatures`, with sent ones reported only if `visible`
+        fn statuses(
+            &self,
+            signatures: &[Signature],
+            visible: bool,
+        ) -> Vec<Option<TransactionStatus>> {
+            let sent = self.sent.borrow();
+            signatures
+                .iter()
+                .map(|signature| {
+                    let landed = sent.iter().any(|tx| tx.signatures[0] == *signature);
+                    (landed && visible).then_some(TransactionStatus {
+                        slot: self.slot,
+                        confirmations: None,
+                        status: Ok(()),
+                        err: None,
+                        confirmation_status: Some(TransactionConfirmationStatus::Confirmed),
+                    })
+                })
+                .collect()
+        }
+    }
+}
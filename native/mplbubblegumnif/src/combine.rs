@@ -0,0 +1,331 @@
+use borsh::BorshDeserialize;
+use mpl_bubblegum::{
+    accounts::TreeConfig, instructions::VerifyCreatorBuilder, types::MetadataArgs,
+};
+use serde::Deserialize;
+use solana_sdk::{
+    instruction::Instruction,
+    message::Message,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use std::time::Instant;
+
+use crate::{
+    config::RpcConfig,
+    das::fetch_leaf_proof,
+    error::NifError,
+    fees::prepend_compute_unit_limit,
+    memo::memo_instruction,
+    transaction::{
+        build_burn_from_proof, build_mint_v1_instruction, build_transfer_from_proof,
+        decode_metadata_base64,
+    },
+    utils::{
+        get_recent_blockhash_with_client, parse_keypair, parse_pubkey, submit_tx_with_client,
+        submit_with_resubmits, SubmitResult,
+    },
+};
+
+/// One instruction to include in a combined transaction, tagged by `kind`.
+///
+/// ```json
+/// [
+///   {"kind": "mint_v1", "tree": "...", "leaf_owner": "...", "metadata_borsh": "...",
+///    "tree_authority": "..."},
+///   {"kind": "transfer", "asset_id": "...", "new_leaf_owner": "...", "canopy_depth": 0},
+///   {"kind": "burn", "asset_id": "..."},
+///   {"kind": "verify_creator", "asset_id": "...", "metadata_borsh": "...", "creator": "..."},
+///   {"kind": "memo", "memo": "..."}
+/// ]
+/// ```
+///
+/// Proofs for `transfer`, `burn` and `verify_creator` are fetched through DAS, so those
+/// leaves must already be indexed; a leaf minted in the same transaction can't be used.
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum InstructionSpec {
+    MintV1 {
+        tree: String,
+        leaf_owner: String,
+        leaf_delegate: Option<String>,
+        metadata_borsh: String,
+        tree_authority: String,
+    },
+    Transfer {
+        asset_id: String,
+        new_leaf_owner: String,
+        #[serde(default)]
+        canopy_depth: u32,
+    },
+    Burn {
+        asset_id: String,
+        #[serde(default)]
+        canopy_depth: u32,
+    },
+    VerifyCreator {
+        asset_id: String,
+        metadata_borsh: String,
+        creator: String,
+        #[serde(default)]
+        canopy_depth: u32,
+    },
+    Memo {
+        memo: String,
+    },
+}
+
+/// Builds every instruction in `instructions_json` into one transaction, signs it with
+/// the matching keypairs from `signer_secret_keys` and submits it.
+///
+/// The first signer pays the fee. Every account the instructions need a signature
+/// from must be in the signer set; signers no instruction needs are left out. The
+/// instructions succeed or fail together.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(rpc_url = %config.rpc_url), err)
+)]
+pub fn combine_instructions(
+    config: &RpcConfig,
+    instructions_json: &str,
+    signer_secret_keys: &[String],
+    confirm: bool,
+    auto_compute_units: bool,
+) -> Result<SubmitResult, NifError> {
+    let started = Instant::now();
+    let specs = parse_instruction_specs(instructions_json)?;
+    let signers = signer_secret_keys
+        .iter()
+        .map(|secret_key| parse_keypair(secret_key))
+        .collect::<Result<Vec<Keypair>, NifError>>()?;
+    let fee_payer = signers
+        .first()
+        .ok_or_else(|| NifError::InvalidKeypair("at least one signer is required".to_string()))?
+        .pubkey();
+
+    let mut instructions = specs
+        .iter()
+        .map(|spec| build_instruction(&config.rpc_url, spec, &fee_payer))
+        .collect::<Result<Vec<Instruction>, NifError>>()?;
+
+    let client = config.client();
+    if auto_compute_units {
+        prepend_compute_unit_limit(&client, &mut instructions, &fee_payer);
+    }
+
+    let recent_blockhash = get_recent_blockhash_with_client(&client)?;
+    submit_with_resubmits(
+        config,
+        &client,
+        &instructions,
+        recent_blockhash,
+        |priced, recent_blockhash| {
+            let message = Message::new(priced, Some(&fee_payer));
+            let required = required_signers(&message, &signers)?;
+            let mut tx = Transaction::new_unsigned(message);
+            tx.try_sign(&required, recent_blockhash)
+                .map_err(|e| NifError::SerializationError(e.to_string()))?;
+            Ok(tx)
+        },
+        |tx| {
+            submit_tx_with_client(
+                &client,
+                tx,
+                confirm,
+                config.skip_preflight,
+                config.rpc_max_retries,
+                config.confirm_timeout,
+            )
+        },
+    )
+    .map(|result| result.with_build_started(started))
+}
+
+/// Parses the JSON list of instruction specs, rejecting an empty list
+pub fn parse_instruction_specs(instructions_json: &str) -> Result<Vec<InstructionSpec>, NifError> {
+    let specs: Vec<InstructionSpec> = serde_json::from_str(instructions_json)
+        .map_err(|e| NifError::InstructionError(format!("JSON parse error: {}", e)))?;
+    if specs.is_empty() {
+        return Err(NifError::InstructionError(
+            "at least one instruction is required".to_string(),
+        ));
+    }
+    Ok(specs)
+}
+
+/// Builds the instruction described by `spec`. Nothing is signed here, so whether the
+/// accounts it needs signatures from can sign is up to the caller.
+pub fn build_instruction(
+    rpc_url: &str,
+    spec: &InstructionSpec,
+    fee_payer: &Pubkey,
+) -> Result<Instruction, NifError> {
+    match spec {
+        InstructionSpec::MintV1 {
+            tree,
+            leaf_owner,
+            leaf_delegate,
+            metadata_borsh,
+            tree_authority,
+        } => {
+            let tree = parse_pubkey(tree)?;
+            let tree_authority = parse_pubkey(tree_authority)?;
+            let metadata_bytes = decode_metadata_base64(metadata_borsh)?;
+            build_mint_v1_instruction(
+                tree,
+                leaf_owner,
+                leaf_delegate.as_deref().unwrap_or(leaf_owner),
+                &metadata_bytes,
+                &tree_authority,
+            )
+        }
+        InstructionSpec::Transfer {
+            asset_id,
+            new_leaf_owner,
+            canopy_depth,
+        } => {
+            let new_owner = parse_pubkey(new_leaf_owner)?;
+            let leaf = fetch_leaf_proof(rpc_url, asset_id)?;
+            Ok(build_transfer_from_proof(
+                &leaf,
+                leaf.owner,
+                new_owner,
+                *canopy_depth,
+            ))
+        }
+        InstructionSpec::Burn {
+            asset_id,
+            canopy_depth,
+        } => {
+            let leaf = fetch_leaf_proof(rpc_url, asset_id)?;
+            Ok(build_burn_from_proof(&leaf, *canopy_depth))
+        }
+        InstructionSpec::VerifyCreator {
+            asset_id,
+            metadata_borsh,
+            creator,
+            canopy_depth,
+        } => {
+            let creator = parse_pubkey(creator)?;
+            let metadata_bytes = decode_metadata_base64(metadata_borsh)?;
+            let metadata = MetadataArgs::try_from_slice(&metadata_bytes).map_err(|e| {
+                NifError::InvalidMetadata(format!("Borsh deserialize error: {}", e))
+            })?;
+            let leaf = fetch_leaf_proof(rpc_url, asset_id)?;
+            let (tree_config, _) = TreeConfig::find_pda(&leaf.tree);
+            Ok(VerifyCreatorBuilder::new()
+                .tree_config(tree_config)
+                .leaf_owner(leaf.owner)
+                .leaf_delegate(leaf.delegate)
+                .merkle_tree(leaf.tree)
+                .payer(*fee_payer)
+                .creator(creator)
+                .root(leaf.root)
+                .data_hash(leaf.data_hash)
+                .creator_hash(leaf.creator_hash)
+                .nonce(leaf.nonce)
+                .index(leaf.index)
+                .metadata(metadata)
+                .add_remaining_accounts(&leaf.proof_accounts(*canopy_depth))
+                .instruction())
+        }
+        InstructionSpec::Memo { memo } => memo_instruction(memo),
+    }
+}
+
+/// Picks the keypairs for every signature `message` requires, in the message's order
+fn required_signers<'a>(
+    message: &Message,
+    signers: &'a [Keypair],
+) -> Result<Vec<&'a Keypair>, NifError> {
+    let num_signers = message.header.num_required_signatures as usize;
+    message.account_keys[..num_signers]
+        .iter()
+        .map(|key| {
+            signers
+                .iter()
+                .find(|signer| signer.pubkey() == *key)
+                .ok_or_else(|| missing_signer(key))
+        })
+        .collect()
+}
+
+fn missing_signer(pubkey: &Pubkey) -> NifError {
+    NifError::KeypairMismatch(format!("{} must sign but is not in the signer set", pubkey))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::instruction::AccountMeta;
+
+    #[test]
+    fn test_parse_instruction_specs() {
+        let json = r#"[
+            {"kind": "transfer", "asset_id": "a", "new_leaf_owner": "b"},
+            {"kind": "memo", "memo": "hello"}
+        ]"#;
+
+        let specs = parse_instruction_specs(json).unwrap();
+        assert_eq!(
+            specs,
+            vec![
+                InstructionSpec::Transfer {
+                    asset_id: "a".to_string(),
+                    new_leaf_owner: "b".to_string(),
+                    canopy_depth: 0,
+                },
+                InstructionSpec::Memo {
+                    memo: "hello".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_instruction_specs_rejects_bad_input() {
+        for json in ["[]", r#"[{"kind": "unknown"}]"#, r#"[{"kind": "burn"}]"#] {
+            let result = parse_instruction_specs(json);
+            assert!(
+                matches!(result, Err(NifError::InstructionError(_))),
+                "{} should be rejected",
+                json
+            );
+        }
+    }
+
+    #[test]
+    fn test_required_signers() {
+        let payer = Keypair::new();
+        let owner = Keypair::new();
+        let unused = Keypair::new();
+        let instruction = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[],
+            vec![AccountMeta::new(owner.pubkey(), true)],
+        );
+        let message = Message::new(&[instruction], Some(&payer.pubkey()));
+
+        let signers = [unused, owner.insecure_clone(), payer.insecure_clone()];
+        let required = required_signers(&message, &signers).unwrap();
+        let required: Vec<Pubkey> = required.iter().map(|signer| signer.pubkey()).collect();
+        assert_eq!(required, vec![payer.pubkey(), owner.pubkey()]);
+
+        let result = required_signers(&message, &signers[..2]);
+        assert!(matches!(result, Err(NifError::KeypairMismatch(_))));
+    }
+
+    #[test]
+    fn test_combine_instructions_requires_a_signer() {
+        let json = r#"[{"kind": "memo", "memo": "hello"}]"#;
+        let result = combine_instructions(
+            &RpcConfig::new("https://api.devnet.solana.com"),
+            json,
+            &[],
+            true,
+            false,
+        );
+        assert!(matches!(result, Err(NifError::InvalidKeypair(_))));
+    }
+}
@@ -0,0 +1,375 @@
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use rustler::{Atom, Encoder, Term};
+use solana_client::rpc_client::{RpcClient, RpcClientConfig};
+use solana_rpc_client::http_sender::HttpSender;
+use solana_sdk::commitment_config::CommitmentConfig;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::{
+    error::NifError,
+    fees::{DEFAULT_MAX_PRIORITY_FEE, DEFAULT_PRIORITY_FEE_ESCALATION},
+    idempotency::DEFAULT_IDEMPOTENCY_TTL_SECS,
+    utils::{
+        is_local_rpc_url, network_from_rpc_url, parse_commitment, primary_rpc_url, rpc_endpoints,
+        rpc_timeout, DEFAULT_CONFIRM_TIMEOUT_SECS, DEFAULT_MAX_LEGACY_ACCOUNTS,
+        DEFAULT_MAX_RESUBMITS, DEFAULT_MAX_SEND_ATTEMPTS, DEFAULT_RETRY_BASE_DELAY_MS,
+    },
+};
+
+/// Connection settings shared by every call that builds and submits a transaction.
+///
+/// Decoded once from an Elixir map at the NIF boundary and passed down by reference,
+/// so the NIFs themselves only take instruction arguments. Every field but `rpc_url`
+/// has a default:
+///
+/// ```elixir
+/// %{
+///   rpc_url: "https://a.example.com,https://b.example.com",
+///   commitment: "confirmed",
+///   timeout_secs: 30,
+///   skip_preflight: false,
+///   priority_fee: 10_000,
+///   headers: %{"x-api-key" => "..."},
+///   allow_mainnet: false,
+///   confirm_timeout_secs: 60,
+///   max_send_attempts: 3,
+///   retry_base_delay_ms: 500,
+///   max_resubmits: 2,
+///   priority_fee_escalation: 1.5,
+///   max_priority_fee: 1_000_000,
+///   max_legacy_accounts: 28,
+///   rpc_max_retries: 5,
+///   idempotency_key: "order-1234",
+///   idempotency_ttl_secs: 600
+/// }
+/// ```
+///
+/// A bare URL string is accepted as well and uses the defaults for everything else.
+/// Only devnet, testnet and local endpoints are used unless `allow_mainnet` is set, so
+/// a misconfigured URL in a test can't spend real SOL.
+#[derive(Debug, Clone)]
+pub struct RpcConfig {
+    /// One or more comma-separated endpoints, tried in order
+    pub rpc_url: String,
+    /// Commitment used for reads and when waiting for confirmation
+    pub commitment: CommitmentConfig,
+    /// Timeout applied to each RPC request
+    pub timeout: Duration,
+    /// Skip the node's preflight simulation when sending
+    pub skip_preflight: bool,
+    /// Compute unit price in micro-lamports added to every transaction, if any
+    pub priority_fee: Option<u64>,
+    /// Extra HTTP headers sent with every request, e.g. a provider API key
+    pub headers: HeaderMap,
+    /// Permit endpoints on mainnet-beta, or on hosts that don't name a cluster
+    pub allow_mainnet: bool,
+    /// How long to wait for a submitted transaction to reach `commitment`
+    pub confirm_timeout: Duration,
+    /// How many times `submit_tx` sends before giving up when no endpoint is available
+    pub max_send_attempts: u32,
+    /// Backoff before the first resend, doubled (and jittered) for each one after it
+    pub retry_base_delay: Duration,
+    /// How many times a transaction whose blockhash expired unconfirmed is re-signed and resent
+    pub max_resubmits: u32,
+    /// Factor the compute unit price is multiplied by on every resubmit
+    pub priority_fee_escalation: f64,
+    /// Ceiling for the escalated compute unit price, in micro-lamports
+    pub max_priority_fee: u64,
+    /// Most accounts a transfer or burn may reference before it has to use lookup tables
+    pub max_legacy_accounts: usize,
+    /// How many times the node rebroadcasts a sent transaction, separate from
+    /// `max_resubmits`; the node's own default when unset
+    pub rpc_max_retries: Option<usize>,
+    /// Caller token under which a mint is remembered, so a retried call returns the
+    /// earlier result instead of minting again
+    pub idempotency_key: Option<String>,
+    /// How long a mint is remembered under its idempotency key
+    pub idempotency_ttl: Duration,
+}
+
+impl RpcConfig {
+    /// Config for `rpc_url` with every other setting at its default
+    pub fn new(rpc_url: &str) -> Self {
+        RpcConfig {
+            rpc_url: rpc_url.to_string(),
+            commitment: CommitmentConfig::default(),
+            timeout: rpc_timeout(None),
+            skip_preflight: false,
+            priority_fee: None,
+            headers: HeaderMap::new(),
+            allow_mainnet: false,
+            confirm_timeout: Duration::from_secs(DEFAULT_CONFIRM_TIMEOUT_SECS),
+            max_send_attempts: DEFAULT_MAX_SEND_ATTEMPTS,
+            retry_base_delay: Duration::from_millis(DEFAULT_RETRY_BASE_DELAY_MS),
+            max_resubmits: DEFAULT_MAX_RESUBMITS,
+            priority_fee_escalation: DEFAULT_PRIORITY_FEE_ESCALATION,
+            max_priority_fee: DEFAULT_MAX_PRIORITY_FEE,
+            max_legacy_accounts: DEFAULT_MAX_LEGACY_ACCOUNTS,
+            rpc_max_retries: None,
+            idempotency_key: None,
+            idempotency_ttl: Duration::from_secs(DEFAULT_IDEMPOTENCY_TTL_SECS),
+        }
+    }
+
+    /// Decodes a config from an Elixir map (atom or string keys) or a bare URL string
+    pub fn from_term(term: Term) -> Result<Self, NifError> {
+        if let Ok(rpc_url) = term.decode::<String>() {
+            let config = RpcConfig::new(&rpc_url);
+            config.ensure_network_allowed()?;
+            return Ok(config);
+        }
+        if !term.is_map() {
+            return Err(NifError::InvalidConfig(
+                "expected a map or an RPC URL".to_string(),
+            ));
+        }
+
+        let rpc_url = config_field(term, "rpc_url")?
+            .ok_or_else(|| NifError::InvalidConfig("missing field `rpc_url`".to_string()))
+            .and_then(decode_rpc_url)?;
+        let mut config = RpcConfig::new(&rpc_url);
+
+        if let Some(commitment) = decode_optional::<String>(term, "commitment")? {
+            config.commitment = parse_commitment(&commitment)?;
+        }
+        if let Some(timeout_secs) = decode_optional::<u64>(term, "timeout_secs")? {
+            config.timeout = rpc_timeout(Some(timeout_secs));
+        }
+        if let Some(skip_preflight) = decode_optional::<bool>(term, "skip_preflight")? {
+            config.skip_preflight = skip_preflight;
+        }
+        config.priority_fee = decode_optional::<u64>(term, "priority_fee")?;
+        if let Some(headers) = decode_optional::<HashMap<String, String>>(term, "headers")? {
+            config.headers = parse_headers(&headers)?;
+        }
+        if let Some(allow_mainnet) = decode_optional::<bool>(term, "allow_mainnet")? {
+            config.allow_mainnet = allow_mainnet;
+        }
+        if let Some(secs) = decode_optional::<u64>(term, "confirm_timeout_secs")? {
+            config.confirm_timeout = Duration::from_secs(secs);
+        }
+        if let Some(attempts) = decode_optional::<u32>(term, "max_send_attempts")? {
+            if attempts == 0 {
+                return Err(invalid_field("max_send_attempts"));
+            }
+            config.max_send_attempts = attempts;
+        }
+        if let Some(delay_ms) = decode_optional::<u64>(term, "retry_base_delay_ms")? {
+            config.retry_base_delay = Duration::from_millis(delay_ms);
+        }
+        if let Some(resubmits) = decode_optional::<u32>(term, "max_resubmits")? {
+            config.max_resubmits = resubmits;
+        }
+        if let Some(factor) = decode_optional::<f64>(term, "priority_fee_escalation")? {
+            if !factor.is_finite() || factor < 1.0 {
+                return Err(invalid_field("priority_fee_escalation"));
+            }
+            config.priority_fee_escalation = factor;
+        }
+        if let Some(max_fee) = decode_optional::<u64>(term, "max_priority_fee")? {
+            config.max_priority_fee = max_fee;
+        }
+        if let Some(max_accounts) = decode_optional::<usize>(term, "max_legacy_accounts")? {
+            config.max_legacy_accounts = max_accounts;
+        }
+        config.rpc_max_retries = decode_optional::<usize>(term, "rpc_max_retries")?;
+        config.idempotency_key = decode_optional::<String>(term, "idempotency_key")?;
+        if let Some(secs) = decode_optional::<u64>(term, "idempotency_ttl_secs")? {
+            config.idempotency_ttl = Duration::from_secs(secs);
+        }
+        config.ensure_network_allowed()?;
+        Ok(config)
+    }
+
+    /// Refuses mainnet-beta endpoints unless `allow_mainnet` is set. Devnet, testnet
+    /// and local validators are always allowed; any other host might be mainnet, so it
+    /// is refused too unless `allow_mainnet` is set.
+    pub fn ensure_network_allowed(&self) -> Result<(), NifError> {
+        if self.allow_mainnet {
+            return Ok(());
+        }
+        for endpoint in rpc_endpoints(&self.rpc_url) {
+            match network_from_rpc_url(endpoint) {
+                Some("devnet" | "testnet") => {}
+                _ if is_local_rpc_url(endpoint) => {}
+                Some(_) => {
+                    return Err(NifError::RpcError(
+                        "mainnet operations require allow_mainnet=true".to_string(),
+                    ))
+                }
+                None => {
+                    return Err(NifError::RpcError(
+                        "can't tell which cluster an RPC endpoint is on; set allow_mainnet=true to use it"
+                            .to_string(),
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Builds a client for the first endpoint in `rpc_url`
+    pub fn client(&self) -> RpcClient {
+        self.client_for(primary_rpc_url(&self.rpc_url))
+    }
+
+    /// Builds a client for `endpoint` using this config's timeout, commitment and headers
+    pub fn client_for(&self, endpoint: &str) -> RpcClient {
+        if self.headers.is_empty() {
+            return RpcClient::new_with_timeout_and_commitment(
+                endpoint.to_string(),
+                self.timeout,
+                self.commitment,
+            );
+        }
+
+        let mut headers = HttpSender::default_headers();
+        headers.extend(self.headers.clone());
+        let client = reqwest::Client::builder()
+            .default_headers(headers)
+            .timeout(self.timeout)
+            .pool_idle_timeout(self.timeout)
+            .build()
+            .expect("build rpc client");
+        RpcClient::new_sender(
+            HttpSender::new_with_client(endpoint, client),
+            RpcClientConfig::with_commitment(self.commitment),
+        )
+    }
+}
+
+/// Accepts `rpc_url` as a comma-separated string or a list of endpoints
+fn decode_rpc_url(value: Term) -> Result<String, NifError> {
+    value
+        .decode::<String>()
+        .or_else(|_| value.decode::<Vec<String>>().map(|urls| urls.join(",")))
+        .map_err(|_| invalid_field("rpc_url"))
+}
+
+/// Validates header names and values, which reqwest would otherwise reject per request
+fn parse_headers(headers: &HashMap<String, String>) -> Result<HeaderMap, NifError> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| NifError::InvalidConfig(format!("invalid header name `{}`", name)))?;
+            let value = HeaderValue::from_str(value).map_err(|_| {
+                NifError::InvalidConfig(format!("invalid value for header `{}`", name))
+            })?;
+            Ok((name, value))
+        })
+        .collect()
+}
+
+/// Looks up `key` in an Elixir map, accepting either an atom or a string key
+fn config_field<'a>(map: Term<'a>, key: &str) -> Result<Option<Term<'a>>, NifError> {
+    let env = map.get_env();
+    let atom_key = Atom::from_str(env, key)
+        .map_err(|_| NifError::InvalidConfig(format!("invalid field name `{}`", key)))?;
+
+    Ok(map
+        .map_get(atom_key.encode(env))
+        .or_else(|_| map.map_get(key.encode(env)))
+        .ok())
+}
+
+/// Decodes an optional field, treating `nil` the same as a missing key
+fn decode_optional<'a, T: rustler::Decoder<'a>>(
+    map: Term<'a>,
+    key: &str,
+) -> Result<Option<T>, NifError> {
+    match config_field(map, key)? {
+        Some(value) => value.decode::<Option<T>>().map_err(|_| invalid_field(key)),
+        None => Ok(None),
+    }
+}
+
+fn invalid_field(key: &str) -> NifError {
+    NifError::InvalidConfig(format!("invalid value for field `{}`", key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rpc_config_defaults() {
+        let config = RpcConfig::new("https://api.devnet.solana.com");
+        assert_eq!(config.commitment, CommitmentConfig::default());
+        assert_eq!(config.timeout, rpc_timeout(None));
+        assert!(!config.skip_preflight);
+        assert!(config.priority_fee.is_none());
+        assert!(config.headers.is_empty());
+        assert!(!config.allow_mainnet);
+        assert_eq!(
+            config.confirm_timeout,
+            Duration::from_secs(DEFAULT_CONFIRM_TIMEOUT_SECS)
+        );
+        assert_eq!(config.max_send_attempts, DEFAULT_MAX_SEND_ATTEMPTS);
+        assert_eq!(config.max_resubmits, DEFAULT_MAX_RESUBMITS);
+        assert_eq!(config.max_priority_fee, DEFAULT_MAX_PRIORITY_FEE);
+    }
+
+    #[test]
+    fn test_ensure_network_allowed() {
+        for rpc_url in [
+            "https://api.devnet.solana.com",
+            "https://api.testnet.solana.com",
+            "http://127.0.0.1:8899",
+            "http://localhost:8899",
+        ] {
+            assert!(RpcConfig::new(rpc_url).ensure_network_allowed().is_ok());
+        }
+
+        // Hosts that can't be told apart from mainnet fail closed
+        for rpc_url in [
+            "http://127.0.0.1:8899,https://api.mainnet-beta.solana.com",
+            "https://rpc.example.com/?cluster=devnet",
+            "https://mainnet-devnet-proxy.example.com",
+        ] {
+            let mut config = RpcConfig::new(rpc_url);
+            assert!(
+                matches!(config.ensure_network_allowed(), Err(NifError::RpcError(_))),
+                "{}",
+                rpc_url
+            );
+            config.allow_mainnet = true;
+            assert!(config.ensure_network_allowed().is_ok());
+        }
+    }
+
+    #[test]
+    fn test_parse_headers() {
+        let headers = HashMap::from([("x-api-key".to_string(), "secret".to_string())]);
+        let parsed = parse_headers(&headers).unwrap();
+        assert_eq!(parsed.get("x-api-key").unwrap(), "secret");
+
+        let headers = HashMap::from([("bad header".to_string(), "secret".to_string())]);
+        assert!(matches!(
+            parse_headers(&headers),
+            Err(NifError::InvalidConfig(_))
+        ));
+
+        let headers = HashMap::from([("x-api-key".to_string(), "line\nbreak".to_string())]);
+        assert!(matches!(
+            parse_headers(&headers),
+            Err(NifError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_client_uses_config() {
+        let mut config = RpcConfig::new("https://a.example.com, https://b.example.com");
+        config.commitment = CommitmentConfig::confirmed();
+        config.headers = parse_headers(&HashMap::from([(
+            "x-api-key".to_string(),
+            "secret".to_string(),
+        )]))
+        .unwrap();
+
+        let client = config.client();
+        assert_eq!(client.url(), "https://a.example.com");
+        assert_eq!(client.commitment(), CommitmentConfig::confirmed());
+    }
+}
@@ -0,0 +1,1069 @@
+use mpl_bubblegum::{types::LeafSchema, utils::get_asset_id};
+use rustler::{types::map::MapIterator, Term};
+use serde_json::{json, Map, Value};
+use solana_sdk::{bs58, instruction::AccountMeta, keccak, pubkey::Pubkey};
+use std::time::Instant;
+
+use crate::{
+    error::NifError,
+    utils::{asset_explorer_url, elapsed_ms, network_from_rpc_url, parse_pubkey, primary_rpc_url},
+};
+
+/// Everything needed to replace or remove a compressed leaf, gathered from DAS
+#[derive(Debug, Clone)]
+pub struct LeafProof {
+    pub tree: Pubkey,
+    pub owner: Pubkey,
+    pub delegate: Pubkey,
+    pub root: [u8; 32],
+    pub data_hash: [u8; 32],
+    pub creator_hash: [u8; 32],
+    pub nonce: u64,
+    pub index: u32,
+    pub proof: Vec<Pubkey>,
+}
+
+impl LeafProof {
+    /// Proof nodes as the read-only remaining accounts Bubblegum expects, leaf to root.
+    ///
+    /// The top `canopy_depth` nodes are cached on-chain in the tree's canopy, so they
+    /// are dropped to keep deep trees under the transaction size limit.
+    pub fn proof_accounts(&self, canopy_depth: u32) -> Vec<AccountMeta> {
+        let keep = self.proof.len().saturating_sub(canopy_depth as usize);
+        self.proof[..keep]
+            .iter()
+            .map(|node| AccountMeta::new_readonly(*node, false))
+            .collect()
+    }
+
+    /// Hash of the leaf as stored in the tree
+    pub fn leaf_hash(&self) -> [u8; 32] {
+        LeafSchema::V1 {
+            id: get_asset_id(&self.tree, self.nonce),
+            owner: self.owner,
+            delegate: self.delegate,
+            nonce: self.nonce,
+            data_hash: self.data_hash,
+            creator_hash: self.creator_hash,
+        }
+        .hash()
+    }
+
+    /// Root the tree would have with this leaf in place, hashed up the full proof
+    pub fn compute_root(&self) -> [u8; 32] {
+        self.proof
+            .iter()
+            .enumerate()
+            .fold(self.leaf_hash(), |node, (level, sibling)| {
+                if (self.index >> level) & 1 == 0 {
+                    keccak::hashv(&[&node, sibling.as_ref()]).to_bytes()
+                } else {
+                    keccak::hashv(&[sibling.as_ref(), &node]).to_bytes()
+                }
+            })
+    }
+
+    /// The same leaf after its delegate changes to `delegate`, with the root updated.
+    ///
+    /// Lets a later instruction in the same transaction act on a leaf an earlier one
+    /// rewrote: the siblings are unchanged, but the tree rejects a proof against a root
+    /// from before the leaf itself was modified.
+    pub fn with_delegate(&self, delegate: Pubkey) -> LeafProof {
+        let mut leaf = LeafProof {
+            delegate,
+            ..self.clone()
+        };
+        leaf.root = leaf.compute_root();
+        leaf
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct DasAsset {
+    compression: DasCompression,
+    ownership: DasOwnership,
+}
+
+#[derive(serde::Deserialize)]
+struct DasCompression {
+    data_hash: String,
+    creator_hash: String,
+    leaf_id: u64,
+    tree: String,
+}
+
+#[derive(serde::Deserialize)]
+struct DasOwnership {
+    owner: String,
+    delegate: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct DasAssetProof {
+    root: String,
+    proof: Vec<String>,
+}
+
+/// Sends a DAS JSON-RPC request to the primary endpoint and returns its `result`.
+/// JSON-RPC errors are surfaced as `NifError::RpcError` carrying their code and message.
+pub fn das_request(rpc_url: &str, method: &str, params: Value) -> Result<Value, NifError> {
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": method,
+        "params": params,
+    });
+
+    let response = reqwest::blocking::Client::new()
+        .post(primary_rpc_url(rpc_url))
+        .json(&request)
+        .send()
+        .map_err(|e| NifError::RpcError(format!("{} failed: {}", method, e)))?;
+
+    // Providers often send a JSON-RPC error body along with a 4xx/5xx status, so the
+    // body is read before the status to keep the more useful message
+    let status = response.status();
+    match response.json::<Value>() {
+        Ok(body) => das_result(method, body),
+        Err(_) if !status.is_success() => Err(NifError::RpcError(format!(
+            "{} failed: HTTP {}",
+            method, status
+        ))),
+        Err(e) => Err(NifError::RpcError(format!(
+            "{} returned an invalid response: {}",
+            method, e
+        ))),
+    }
+}
+
+/// Extracts the `result` of a JSON-RPC response, mapping its `error` field if present
+fn das_result(method: &str, mut body: Value) -> Result<Value, NifError> {
+    if let Some(error) = body.get("error") {
+        let code = error.get("code").and_then(Value::as_i64);
+        let message = error
+            .get("message")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .unwrap_or_else(|| error.to_string());
+        return Err(NifError::RpcError(match code {
+            Some(code) => format!("{} failed ({}): {}", method, code, message),
+            None => format!("{} failed: {}", method, message),
+        }));
+    }
+    Ok(body
+        .get_mut("result")
+        .map(Value::take)
+        .unwrap_or(Value::Null))
+}
+
+/// JSON-RPC code for a method the endpoint doesn't implement
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// Whether a DAS call failed because the endpoint doesn't support DAS at all
+pub fn is_method_not_found(error: &NifError) -> bool {
+    match error {
+        NifError::RpcError(message) => {
+            message.contains(&format!("({})", METHOD_NOT_FOUND))
+                || message.to_lowercase().contains("method not found")
+        }
+        _ => false,
+    }
+}
+
+/// Fetches an asset through the DAS `getAsset` method, failing if it does not exist.
+///
+/// An `explorer_url` for the asset is added when the cluster can be told from
+/// `rpc_url`.
+pub fn get_asset(rpc_url: &str, asset_id: &str) -> Result<Value, NifError> {
+    let asset_id = parse_pubkey(asset_id)?;
+    match das_request(rpc_url, "getAsset", json!({ "id": asset_id.to_string() }))? {
+        Value::Null => Err(NifError::RpcError(format!("asset {} not found", asset_id))),
+        mut asset => {
+            add_explorer_url(rpc_url, &asset_id, &mut asset);
+            Ok(asset)
+        }
+    }
+}
+
+/// Adds the asset's explorer link to a DAS asset object, if the network is known
+fn add_explorer_url(rpc_url: &str, asset_id: &Pubkey, asset: &mut Value) {
+    let url = network_from_rpc_url(primary_rpc_url(rpc_url))
+        .and_then(|network| asset_explorer_url(&asset_id.to_string(), network).ok());
+    if let (Some(url), Some(asset)) = (url, asset.as_object_mut()) {
+        asset.insert("explorer_url".to_string(), Value::String(url));
+    }
+}
+
+/// Fetches the merkle proof of an asset through the DAS `getAssetProof` method
+pub fn get_asset_proof(rpc_url: &str, asset_id: &str) -> Result<Value, NifError> {
+    let asset_id = parse_pubkey(asset_id)?;
+    match das_request(
+        rpc_url,
+        "getAssetProof",
+        json!({ "id": asset_id.to_string() }),
+    )? {
+        Value::Null => Err(NifError::RpcError(format!(
+            "no proof found for asset {}",
+            asset_id
+        ))),
+        proof => Ok(proof),
+    }
+}
+
+/// Fetches several assets in one DAS `getAssetBatch` request, in the order of
+/// `asset_ids`, with `Value::Null` in place of any asset that doesn't exist
+pub fn get_asset_batch(rpc_url: &str, asset_ids: &[String]) -> Result<Vec<Value>, NifError> {
+    let asset_ids = asset_ids
+        .iter()
+        .map(|asset_id| parse_pubkey(asset_id).map(|asset_id| asset_id.to_string()))
+        .collect::<Result<Vec<String>, NifError>>()?;
+    if asset_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let assets = das_request(rpc_url, "getAssetBatch", json!({ "ids": asset_ids }))?;
+    Ok(align_asset_batch(&asset_ids, assets))
+}
+
+/// Lines up a `getAssetBatch` result with the requested ids by each asset's `id`,
+/// since providers may drop or reorder missing assets
+fn align_asset_batch(asset_ids: &[String], assets: Value) -> Vec<Value> {
+    let mut assets = match assets {
+        Value::Array(assets) => assets,
+        _ => Vec::new(),
+    };
+    asset_ids
+        .iter()
+        .map(|asset_id| {
+            assets
+                .iter_mut()
+                .find(|asset| asset["id"].as_str() == Some(asset_id.as_str()))
+                .map(Value::take)
+                .unwrap_or(Value::Null)
+        })
+        .collect()
+}
+
+/// Each requested asset id paired with its proof, or why there is none
+pub type ProofBatch = Vec<(String, Result<Value, NifError>)>;
+
+/// Fetches the proofs of several assets in one DAS `getAssetProofBatch` request.
+///
+/// Results come back in the order of `asset_ids`, each with its own outcome, so an
+/// invalid or unknown id doesn't fail the rest of the batch.
+pub fn get_asset_proof_batch(rpc_url: &str, asset_ids: &[String]) -> Result<ProofBatch, NifError> {
+    let valid_ids: Vec<String> = asset_ids
+        .iter()
+        .filter_map(|asset_id| parse_pubkey(asset_id).ok())
+        .map(|asset_id| asset_id.to_string())
+        .collect();
+    let proofs = if valid_ids.is_empty() {
+        Value::Null
+    } else {
+        das_request(rpc_url, "getAssetProofBatch", json!({ "ids": valid_ids }))?
+    };
+    Ok(proof_batch_from_json(asset_ids, proofs))
+}
+
+/// Pairs each requested id with its proof from a `getAssetProofBatch` result
+fn proof_batch_from_json(asset_ids: &[String], mut proofs: Value) -> ProofBatch {
+    asset_ids
+        .iter()
+        .map(|asset_id| {
+            let proof = parse_pubkey(asset_id).and_then(|pubkey| {
+                match proofs.get_mut(pubkey.to_string()).map(Value::take) {
+                    Some(Value::Null) | None => Err(NifError::RpcError(format!(
+                        "no proof found for asset {}",
+                        pubkey
+                    ))),
+                    Some(proof) => Ok(proof),
+                }
+            });
+            (asset_id.clone(), proof)
+        })
+        .collect()
+}
+
+/// One page of assets returned by a DAS listing method, with how long the request took
+#[derive(Debug, Clone)]
+pub struct AssetPage {
+    pub total: u64,
+    pub page: u32,
+    pub limit: u32,
+    pub items: Vec<Value>,
+    pub duration_ms: u64,
+}
+
+/// Lists the assets owned by a wallet through the DAS `getAssetsByOwner` method.
+/// Pages start at 1; callers loop until a page comes back with fewer than `limit` items.
+pub fn get_assets_by_owner(
+    rpc_url: &str,
+    owner: &str,
+    page: u32,
+    limit: u32,
+) -> Result<AssetPage, NifError> {
+    let owner = parse_pubkey(owner)?;
+    let params = json!({
+        "ownerAddress": owner.to_string(),
+        "page": page,
+        "limit": limit,
+    });
+    fetch_asset_page(rpc_url, "getAssetsByOwner", params)
+}
+
+/// Lists the assets in a group, usually `("collection", <collection mint>)`, through
+/// the DAS `getAssetsByGroup` method. Paging works as in `get_assets_by_owner`.
+pub fn get_assets_by_group(
+    rpc_url: &str,
+    group_key: &str,
+    group_value: &str,
+    page: u32,
+    limit: u32,
+) -> Result<AssetPage, NifError> {
+    let group_value = parse_pubkey(group_value)?;
+    let params = json!({
+        "groupKey": group_key.trim(),
+        "groupValue": group_value.to_string(),
+        "page": page,
+        "limit": limit,
+    });
+    fetch_asset_page(rpc_url, "getAssetsByGroup", params)
+}
+
+/// Lists the assets a creator appears on, across every tree, through the DAS
+/// `getAssetsByCreator` method. With `only_verified` set, assets where the creator is
+/// listed but unverified are left out. Paging works as in `get_assets_by_owner`.
+pub fn get_assets_by_creator(
+    rpc_url: &str,
+    creator: &str,
+    only_verified: bool,
+    page: u32,
+    limit: u32,
+) -> Result<AssetPage, NifError> {
+    let creator = parse_pubkey(creator)?;
+    let params = json!({
+        "creatorAddress": creator.to_string(),
+        "onlyVerified": only_verified,
+        "page": page,
+        "limit": limit,
+    });
+    fetch_asset_page(rpc_url, "getAssetsByCreator", params)
+}
+
+/// Lists the assets whose update authority is `authority` through the DAS
+/// `getAssetsByAuthority` method. Paging works as in `get_assets_by_owner`.
+pub fn get_assets_by_authority(
+    rpc_url: &str,
+    authority: &str,
+    page: u32,
+    limit: u32,
+) -> Result<AssetPage, NifError> {
+    let authority = parse_pubkey(authority)?;
+    let params = json!({
+        "authorityAddress": authority.to_string(),
+        "page": page,
+        "limit": limit,
+    });
+    fetch_asset_page(rpc_url, "getAssetsByAuthority", params)
+}
+
+/// `searchAssets` parameters that may be passed through as filters
+const SEARCH_ASSETS_FILTERS: &[&str] = &[
+    "ownerAddress",
+    "ownerType",
+    "creatorAddress",
+    "creatorVerified",
+    "authorityAddress",
+    "grouping",
+    "delegate",
+    "frozen",
+    "supply",
+    "supplyMint",
+    "compressed",
+    "compressible",
+    "royaltyTargetType",
+    "royaltyTarget",
+    "royaltyAmount",
+    "burnt",
+    "jsonUri",
+    "interface",
+    "negate",
+    "conditionType",
+    "sortBy",
+];
+
+/// Runs a compound query through the DAS `searchAssets` method, e.g.
+/// `%{ownerAddress: owner, grouping: ["collection", mint], compressed: true}`.
+/// Filter names follow DAS and anything outside `SEARCH_ASSETS_FILTERS` is rejected.
+/// Paging works as in `get_assets_by_owner`.
+pub fn search_assets(
+    rpc_url: &str,
+    filters: Map<String, Value>,
+    page: u32,
+    limit: u32,
+) -> Result<AssetPage, NifError> {
+    if let Some(key) = filters
+        .keys()
+        .find(|key| !SEARCH_ASSETS_FILTERS.contains(&key.as_str()))
+    {
+        return Err(NifError::InvalidMetadata(format!(
+            "unknown searchAssets filter `{}`",
+            key
+        )));
+    }
+
+    let mut params = filters;
+    params.insert("page".to_string(), json!(page));
+    params.insert("limit".to_string(), json!(limit));
+    fetch_asset_page(rpc_url, "searchAssets", Value::Object(params))
+}
+
+/// Decodes an Elixir map of `searchAssets` filters, keyed by atoms or strings
+pub fn search_filters_from_term(term: Term) -> Result<Map<String, Value>, NifError> {
+    let entries = MapIterator::new(term)
+        .ok_or_else(|| NifError::InvalidMetadata("filters must be a map".to_string()))?;
+    entries
+        .map(|(key, value)| {
+            let key = key
+                .atom_to_string()
+                .or_else(|_| key.decode::<String>())
+                .map_err(|_| {
+                    NifError::InvalidMetadata("filter names must be atoms or strings".to_string())
+                })?;
+            let value = filter_value_to_json(value).map_err(|_| {
+                NifError::InvalidMetadata(format!("invalid value for filter `{}`", key))
+            })?;
+            Ok((key, value))
+        })
+        .collect()
+}
+
+/// Converts a filter value (boolean, number, string, atom, list or map) to JSON
+fn filter_value_to_json(term: Term) -> Result<Value, NifError> {
+    if let Ok(value) = term.decode::<bool>() {
+        return Ok(Value::Bool(value));
+    }
+    if let Ok(value) = term.decode::<i64>() {
+        return Ok(json!(value));
+    }
+    if let Ok(value) = term.decode::<f64>() {
+        return Ok(json!(value));
+    }
+    if let Ok(value) = term.decode::<String>() {
+        return Ok(Value::String(value));
+    }
+    if let Ok(atom) = term.atom_to_string() {
+        return Ok(match atom.as_str() {
+            "nil" => Value::Null,
+            _ => Value::String(atom),
+        });
+    }
+    if let Ok(items) = term.decode::<Vec<Term>>() {
+        return items
+            .into_iter()
+            .map(filter_value_to_json)
+            .collect::<Result<Vec<Value>, NifError>>()
+            .map(Value::Array);
+    }
+    if term.is_map() {
+        return search_filters_from_term(term).map(Value::Object);
+    }
+    Err(NifError::InvalidMetadata(
+        "unsupported filter value".to_string(),
+    ))
+}
+
+/// Calls a DAS listing method and times the round trip
+fn fetch_asset_page(rpc_url: &str, method: &str, params: Value) -> Result<AssetPage, NifError> {
+    let started = Instant::now();
+    let mut page = asset_page_from_json(das_request(rpc_url, method, params)?)?;
+    page.duration_ms = elapsed_ms(started);
+    Ok(page)
+}
+
+/// Builds `AssetPage` from the result of a DAS listing method
+fn asset_page_from_json(result: Value) -> Result<AssetPage, NifError> {
+    #[derive(serde::Deserialize)]
+    struct DasAssetList {
+        total: u64,
+        page: u32,
+        limit: u32,
+        items: Vec<Value>,
+    }
+
+    let list: DasAssetList = serde_json::from_value(result)
+        .map_err(|e| NifError::SerializationError(format!("asset list response: {}", e)))?;
+    Ok(AssetPage {
+        total: list.total,
+        page: list.page,
+        limit: list.limit,
+        items: list.items,
+        duration_ms: 0,
+    })
+}
+
+/// A transaction that touched a compressed asset, e.g. its mint or a transfer
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompressionSignature {
+    pub signature: String,
+    /// Bubblegum instruction name as reported by DAS, e.g. `"Transfer"`
+    pub instruction: String,
+}
+
+/// Lists the transactions that changed a compressed asset through the DAS
+/// `getCompressionSignaturesForAsset` method, newest first. Paging works as in
+/// `get_assets_by_owner`.
+pub fn get_compression_signatures_for_asset(
+    rpc_url: &str,
+    asset_id: &str,
+    page: u32,
+    limit: u32,
+) -> Result<Vec<CompressionSignature>, NifError> {
+    let asset_id = parse_pubkey(asset_id)?;
+    let params = json!({
+        "id": asset_id.to_string(),
+        "page": page,
+        "limit": limit,
+    });
+    compression_signatures_from_json(das_request(
+        rpc_url,
+        "getCompressionSignaturesForAsset",
+        params,
+    )?)
+}
+
+/// Reads the `[signature, instruction]` pairs out of a signatures result
+fn compression_signatures_from_json(result: Value) -> Result<Vec<CompressionSignature>, NifError> {
+    #[derive(serde::Deserialize)]
+    struct DasSignatureList {
+        items: Vec<(String, String)>,
+    }
+
+    let list: DasSignatureList = serde_json::from_value(result)
+        .map_err(|e| NifError::SerializationError(format!("signature list response: {}", e)))?;
+    Ok(list
+        .items
+        .into_iter()
+        .map(|(signature, instruction)| CompressionSignature {
+            signature,
+            instruction,
+        })
+        .collect())
+}
+
+/// Fetches an asset and its proof and assembles the leaf proof inputs
+pub fn fetch_leaf_proof(rpc_url: &str, asset_id: &str) -> Result<LeafProof, NifError> {
+    let asset = get_asset(rpc_url, asset_id)?;
+    let proof = get_asset_proof(rpc_url, asset_id)?;
+    leaf_proof_from_json(asset, proof)
+}
+
+/// Like [`fetch_leaf_proof`], but checks the asset is owned by `owner` before the
+/// proof is fetched, so a wrong keypair fails fast with `KeypairMismatch`
+pub fn fetch_owned_leaf_proof(
+    rpc_url: &str,
+    asset_id: &str,
+    owner: &Pubkey,
+) -> Result<LeafProof, NifError> {
+    let asset = get_asset(rpc_url, asset_id)?;
+    let asset_owner = asset["ownership"]["owner"]
+        .as_str()
+        .ok_or_else(|| NifError::SerializationError("getAsset response: missing owner".to_string()))
+        .and_then(parse_pubkey)?;
+    ensure_asset_owner(asset_id, &asset_owner, owner)?;
+
+    let proof = get_asset_proof(rpc_url, asset_id)?;
+    leaf_proof_from_json(asset, proof)
+}
+
+/// Fails with `KeypairMismatch` unless `owner` is the asset's current owner
+pub fn ensure_asset_owner(
+    asset_id: &str,
+    asset_owner: &Pubkey,
+    owner: &Pubkey,
+) -> Result<(), NifError> {
+    if asset_owner != owner {
+        return Err(NifError::KeypairMismatch(format!(
+            "asset {} is owned by {}, not {}",
+            asset_id.trim(),
+            asset_owner,
+            owner
+        )));
+    }
+    Ok(())
+}
+
+/// Fetches the leaf proofs of several assets at once, running the `getAssetBatch` and
+/// `getAssetProofBatch` requests in parallel. Each id gets its own result in input
+/// order, so one invalid or unknown asset doesn't fail the others.
+pub fn fetch_leaf_proof_batch(
+    rpc_url: &str,
+    asset_ids: &[String],
+) -> Result<Vec<Result<LeafProof, NifError>>, NifError> {
+    let valid_ids: Vec<String> = asset_ids
+        .iter()
+        .filter_map(|asset_id| parse_pubkey(asset_id).ok())
+        .map(|asset_id| asset_id.to_string())
+        .collect();
+
+    let (assets, proofs) = std::thread::scope(|scope| {
+        let assets = scope.spawn(|| get_asset_batch(rpc_url, &valid_ids));
+        let proofs = get_asset_proof_batch(rpc_url, &valid_ids);
+        (
+            assets.join().expect("getAssetBatch thread panicked"),
+            proofs,
+        )
+    });
+    let mut assets = assets?.into_iter();
+    let mut proofs = proofs?.into_iter();
+
+    // Both batches follow `valid_ids`, so they advance only for ids that parsed
+    Ok(asset_ids
+        .iter()
+        .map(|asset_id| {
+            let asset_id = parse_pubkey(asset_id)?;
+            let asset = assets.next().unwrap_or(Value::Null);
+            let proof = proofs
+                .next()
+                .map(|(_, proof)| proof)
+                .unwrap_or_else(|| Ok(Value::Null))?;
+            if asset.is_null() {
+                return Err(NifError::RpcError(format!("asset {} not found", asset_id)));
+            }
+            leaf_proof_from_json(asset, proof)
+        })
+        .collect())
+}
+
+/// Builds `LeafProof` from `getAsset` and `getAssetProof` results
+fn leaf_proof_from_json(asset: Value, proof: Value) -> Result<LeafProof, NifError> {
+    let asset: DasAsset = serde_json::from_value(asset)
+        .map_err(|e| NifError::SerializationError(format!("getAsset response: {}", e)))?;
+    let proof: DasAssetProof = serde_json::from_value(proof)
+        .map_err(|e| NifError::SerializationError(format!("getAssetProof response: {}", e)))?;
+
+    let owner = parse_pubkey(&asset.ownership.owner)?;
+    let delegate = match asset.ownership.delegate {
+        Some(delegate) => parse_pubkey(&delegate)?,
+        None => owner,
+    };
+    let index = u32::try_from(asset.compression.leaf_id).map_err(|_| {
+        NifError::SerializationError(format!(
+            "leaf_id {} does not fit a leaf index",
+            asset.compression.leaf_id
+        ))
+    })?;
+
+    Ok(LeafProof {
+        tree: parse_pubkey(&asset.compression.tree)?,
+        owner,
+        delegate,
+        root: decode_hash(&proof.root)?,
+        data_hash: decode_hash(&asset.compression.data_hash)?,
+        creator_hash: decode_hash(&asset.compression.creator_hash)?,
+        nonce: asset.compression.leaf_id,
+        index,
+        proof: proof
+            .proof
+            .iter()
+            .map(|node| parse_pubkey(node))
+            .collect::<Result<Vec<Pubkey>, NifError>>()?,
+    })
+}
+
+/// Decodes a base58-encoded 32-byte hash as reported by DAS
+pub fn decode_hash(hash: &str) -> Result<[u8; 32], NifError> {
+    let bytes = bs58::decode(hash.trim())
+        .into_vec()
+        .map_err(|e| NifError::SerializationError(format!("Base58 decode error: {}", e)))?;
+    <[u8; 32]>::try_from(bytes.as_slice()).map_err(|_| {
+        NifError::SerializationError(format!(
+            "expected a 32-byte hash, got {} bytes",
+            bytes.len()
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_leaf() -> LeafProof {
+        let mut leaf = LeafProof {
+            tree: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            delegate: Pubkey::new_unique(),
+            root: [0; 32],
+            data_hash: [1; 32],
+            creator_hash: [2; 32],
+            nonce: 5,
+            index: 5,
+            proof: (0..3).map(|_| Pubkey::new_unique()).collect(),
+        };
+        leaf.root = leaf.compute_root();
+        leaf
+    }
+
+    #[test]
+    fn test_add_explorer_url() {
+        let asset_id = Pubkey::new_unique();
+
+        let mut asset = json!({ "id": asset_id.to_string() });
+        add_explorer_url("https://api.devnet.solana.com", &asset_id, &mut asset);
+        assert_eq!(
+            asset["explorer_url"],
+            format!(
+                "https://explorer.solana.com/address/{}?cluster=devnet",
+                asset_id
+            )
+        );
+
+        let mut asset = json!({ "id": asset_id.to_string() });
+        add_explorer_url("http://127.0.0.1:8899", &asset_id, &mut asset);
+        assert!(asset.get("explorer_url").is_none());
+    }
+
+    #[test]
+    fn test_leaf_proof_with_delegate() {
+        let leaf = sample_leaf();
+        let delegate = Pubkey::new_unique();
+
+        let delegated = leaf.with_delegate(delegate);
+        assert_eq!(delegated.delegate, delegate);
+        assert_eq!(delegated.proof, leaf.proof);
+        assert_ne!(delegated.root, leaf.root);
+        assert_ne!(delegated.leaf_hash(), leaf.leaf_hash());
+
+        // Restoring the delegate restores the root
+        assert_eq!(delegated.with_delegate(leaf.delegate).root, leaf.root);
+    }
+
+    #[test]
+    fn test_compute_root_orders_siblings_by_index() {
+        let leaf = sample_leaf();
+        let node = keccak::hashv(&[leaf.proof[0].as_ref(), &leaf.leaf_hash()]).to_bytes();
+        let node = keccak::hashv(&[&node, leaf.proof[1].as_ref()]).to_bytes();
+        let root = keccak::hashv(&[leaf.proof[2].as_ref(), &node]).to_bytes();
+        assert_eq!(leaf.compute_root(), root);
+    }
+
+    fn sample_asset(owner: &Pubkey, delegate: Option<&Pubkey>, tree: &Pubkey) -> Value {
+        json!({
+            "id": Pubkey::new_unique().to_string(),
+            "compression": {
+                "data_hash": bs58::encode([1u8; 32]).into_string(),
+                "creator_hash": bs58::encode([2u8; 32]).into_string(),
+                "leaf_id": 5,
+                "tree": tree.to_string(),
+                "compressed": true
+            },
+            "ownership": {
+                "owner": owner.to_string(),
+                "delegate": delegate.map(|d| d.to_string()),
+                "delegated": delegate.is_some()
+            }
+        })
+    }
+
+    fn sample_proof(tree: &Pubkey, nodes: &[Pubkey]) -> Value {
+        json!({
+            "root": bs58::encode([3u8; 32]).into_string(),
+            "proof": nodes.iter().map(|n| n.to_string()).collect::<Vec<_>>(),
+            "node_index": 16389,
+            "tree_id": tree.to_string()
+        })
+    }
+
+    #[test]
+    fn test_leaf_proof_from_json() {
+        let owner = Pubkey::new_unique();
+        let tree = Pubkey::new_unique();
+        let nodes = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+        let result = leaf_proof_from_json(
+            sample_asset(&owner, None, &tree),
+            sample_proof(&tree, &nodes),
+        );
+        assert!(result.is_ok(), "Failed to parse proof: {:?}", result.err());
+        let leaf = result.unwrap();
+        assert_eq!(leaf.tree, tree);
+        assert_eq!(leaf.owner, owner);
+        assert_eq!(leaf.delegate, owner, "Delegate should default to owner");
+        assert_eq!(leaf.data_hash, [1u8; 32]);
+        assert_eq!(leaf.creator_hash, [2u8; 32]);
+        assert_eq!(leaf.root, [3u8; 32]);
+        assert_eq!(leaf.nonce, 5);
+        assert_eq!(leaf.index, 5);
+        assert_eq!(leaf.proof, nodes);
+    }
+
+    #[test]
+    fn test_leaf_proof_from_json_with_delegate() {
+        let owner = Pubkey::new_unique();
+        let delegate = Pubkey::new_unique();
+        let tree = Pubkey::new_unique();
+
+        let leaf = leaf_proof_from_json(
+            sample_asset(&owner, Some(&delegate), &tree),
+            sample_proof(&tree, &[]),
+        )
+        .expect("Failed to parse proof");
+        assert_eq!(leaf.delegate, delegate);
+    }
+
+    #[test]
+    fn test_fetch_leaf_proof_batch_invalid_ids() {
+        let ids = ["invalid".to_string(), "also invalid".to_string()];
+        let results = fetch_leaf_proof_batch("http://127.0.0.1:1", &ids).unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results
+            .iter()
+            .all(|result| matches!(result, Err(NifError::InvalidPubkey(_)))));
+    }
+
+    #[test]
+    fn test_ensure_asset_owner() {
+        let owner = Pubkey::new_unique();
+        assert!(ensure_asset_owner("asset", &owner, &owner).is_ok());
+        assert!(matches!(
+            ensure_asset_owner("asset", &owner, &Pubkey::new_unique()),
+            Err(NifError::KeypairMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_leaf_proof_from_json_missing_fields() {
+        let result = leaf_proof_from_json(json!({}), json!({}));
+        assert!(result.is_err(), "Should fail with missing fields");
+        if let Err(NifError::SerializationError(_)) = result {
+            // Success
+        } else {
+            panic!("Wrong error type");
+        }
+    }
+
+    #[test]
+    fn test_proof_accounts_trims_canopy() {
+        let owner = Pubkey::new_unique();
+        let tree = Pubkey::new_unique();
+        let nodes: Vec<Pubkey> = (0..5).map(|_| Pubkey::new_unique()).collect();
+        let leaf = leaf_proof_from_json(
+            sample_asset(&owner, None, &tree),
+            sample_proof(&tree, &nodes),
+        )
+        .expect("Failed to parse proof");
+
+        let untrimmed = leaf.proof_accounts(0);
+        assert_eq!(untrimmed.len(), 5);
+
+        let trimmed = leaf.proof_accounts(3);
+        assert_eq!(trimmed.len(), 2);
+        assert_eq!(
+            trimmed[0].pubkey, nodes[0],
+            "Nodes nearest the leaf are kept"
+        );
+        assert_eq!(trimmed[1].pubkey, nodes[1]);
+        assert!(trimmed
+            .iter()
+            .all(|meta| !meta.is_writable && !meta.is_signer));
+
+        assert!(leaf.proof_accounts(10).is_empty());
+    }
+
+    #[test]
+    fn test_asset_page_from_json() {
+        let result = json!({
+            "total": 2,
+            "limit": 10,
+            "page": 1,
+            "items": [{ "id": "a" }, { "id": "b" }]
+        });
+
+        let page = asset_page_from_json(result).expect("Failed to parse asset page");
+        assert_eq!(page.total, 2);
+        assert_eq!(page.page, 1);
+        assert_eq!(page.limit, 10);
+        assert_eq!(page.items.len(), 2);
+    }
+
+    #[test]
+    fn test_compression_signatures_from_json() {
+        let result = json!({
+            "total": 2,
+            "limit": 10,
+            "page": 1,
+            "items": [["sig2", "Transfer"], ["sig1", "MintV1"]]
+        });
+
+        let signatures =
+            compression_signatures_from_json(result).expect("Failed to parse signatures");
+        assert_eq!(
+            signatures,
+            vec![
+                CompressionSignature {
+                    signature: "sig2".to_string(),
+                    instruction: "Transfer".to_string(),
+                },
+                CompressionSignature {
+                    signature: "sig1".to_string(),
+                    instruction: "MintV1".to_string(),
+                },
+            ]
+        );
+        assert!(compression_signatures_from_json(json!({})).is_err());
+    }
+
+    #[test]
+    fn test_get_assets_by_owner_invalid_owner() {
+        let result = get_assets_by_owner("http://127.0.0.1:1", "invalid_owner", 1, 10);
+        assert!(result.is_err(), "Should fail with invalid owner");
+        if let Err(NifError::InvalidPubkey(_)) = result {
+            // Success
+        } else {
+            panic!("Wrong error type");
+        }
+    }
+
+    #[test]
+    fn test_get_assets_by_group_invalid_group_value() {
+        let result = get_assets_by_group("http://127.0.0.1:1", "collection", "invalid", 1, 10);
+        assert!(result.is_err(), "Should fail with invalid group value");
+        if let Err(NifError::InvalidPubkey(_)) = result {
+            // Success
+        } else {
+            panic!("Wrong error type");
+        }
+    }
+
+    #[test]
+    fn test_get_assets_by_creator_invalid_creator() {
+        let result = get_assets_by_creator("http://127.0.0.1:1", "invalid_creator", true, 1, 10);
+        assert!(matches!(result, Err(NifError::InvalidPubkey(_))));
+    }
+
+    #[test]
+    fn test_get_assets_by_authority_invalid_authority() {
+        let result = get_assets_by_authority("http://127.0.0.1:1", "invalid_authority", 1, 10);
+        assert!(matches!(result, Err(NifError::InvalidPubkey(_))));
+    }
+
+    #[test]
+    fn test_search_assets_rejects_unknown_filter() {
+        let filters = json!({ "ownerAddress": Pubkey::new_unique().to_string(), "page": 2 });
+        let filters = filters.as_object().unwrap().clone();
+
+        let result = search_assets("http://127.0.0.1:1", filters, 1, 10);
+        match result {
+            Err(NifError::InvalidMetadata(msg)) => assert!(msg.contains("`page`"), "{}", msg),
+            other => panic!("Wrong result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_align_asset_batch() {
+        let first = Pubkey::new_unique().to_string();
+        let missing = Pubkey::new_unique().to_string();
+        let last = Pubkey::new_unique().to_string();
+        let assets = json!([{ "id": last }, null, { "id": first }]);
+
+        let aligned = align_asset_batch(&[first.clone(), missing, last.clone()], assets);
+        assert_eq!(
+            aligned,
+            vec![json!({ "id": first }), Value::Null, json!({ "id": last })]
+        );
+    }
+
+    #[test]
+    fn test_get_asset_batch_invalid_asset_id() {
+        let ids = [Pubkey::new_unique().to_string(), "invalid".to_string()];
+        let result = get_asset_batch("http://127.0.0.1:1", &ids);
+        assert!(matches!(result, Err(NifError::InvalidPubkey(_))));
+    }
+
+    #[test]
+    fn test_proof_batch_from_json() {
+        let found = Pubkey::new_unique();
+        let missing = Pubkey::new_unique();
+        let tree = Pubkey::new_unique();
+        let proofs = json!({
+            found.to_string(): sample_proof(&tree, &[]),
+            missing.to_string(): null
+        });
+        let asset_ids = [
+            found.to_string(),
+            missing.to_string(),
+            "invalid_asset_id".to_string(),
+        ];
+
+        let results = proof_batch_from_json(&asset_ids, proofs);
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].0, asset_ids[0]);
+        assert_eq!(results[0].1.as_ref().unwrap(), &sample_proof(&tree, &[]));
+        assert!(matches!(results[1].1, Err(NifError::RpcError(_))));
+        assert!(matches!(results[2].1, Err(NifError::InvalidPubkey(_))));
+    }
+
+    #[test]
+    fn test_das_result_returns_result() {
+        let body = json!({ "jsonrpc": "2.0", "id": 1, "result": { "total": 0 } });
+        let result = das_result("getAssetsByOwner", body).expect("Should return result");
+        assert_eq!(result, json!({ "total": 0 }));
+    }
+
+    #[test]
+    fn test_das_result_maps_error() {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "error": { "code": -32000, "message": "Asset Not Found" }
+        });
+        let result = das_result("getAsset", body);
+        if let Err(NifError::RpcError(msg)) = result {
+            assert_eq!(msg, "getAsset failed (-32000): Asset Not Found");
+        } else {
+            panic!("Wrong error type");
+        }
+    }
+
+    #[test]
+    fn test_is_method_not_found() {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "error": { "code": -32601, "message": "Method not found" }
+        });
+        assert!(is_method_not_found(
+            &das_result("getAsset", body).unwrap_err()
+        ));
+
+        let not_found = NifError::RpcError("getAsset failed (-32000): Asset Not Found".to_string());
+        assert!(!is_method_not_found(&not_found));
+        assert!(!is_method_not_found(&NifError::Timeout(
+            "getAsset".to_string()
+        )));
+    }
+
+    #[test]
+    fn test_das_request_unreachable_endpoint() {
+        let result = das_request("http://127.0.0.1:1", "getAsset", json!({ "id": "x" }));
+        if let Err(NifError::RpcError(msg)) = result {
+            assert!(
+                msg.starts_with("getAsset failed"),
+                "Unexpected error: {}",
+                msg
+            );
+        } else {
+            panic!("Wrong error type");
+        }
+    }
+
+    #[test]
+    fn test_decode_hash_wrong_length() {
+        let result = decode_hash(&bs58::encode([0u8; 16]).into_string());
+        assert!(result.is_err(), "Should fail with a 16-byte hash");
+        if let Err(NifError::SerializationError(msg)) = result {
+            assert!(msg.contains("16 bytes"));
+        } else {
+            panic!("Wrong error type");
+        }
+    }
+}
@@ -0,0 +1,272 @@
+use borsh::BorshDeserialize;
+use mpl_bubblegum::{
+    types::{BubblegumEventType, LeafSchema},
+    utils::get_asset_id,
+    LeafSchemaEvent,
+};
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_sdk::{bs58, pubkey::Pubkey};
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, EncodedTransaction, UiInnerInstructions,
+    UiInstruction, UiLoadedAddresses, UiMessage, UiTransactionEncoding,
+};
+
+use crate::{
+    config::RpcConfig,
+    das::LeafProof,
+    error::NifError,
+    tree::{
+        canopy_depth_from_account_data, fetch_merkle_tree_data, recent_roots_from_account_data,
+        rightmost_leaf_from_account_data,
+    },
+    utils::{classify_client_error, parse_pubkey, parse_signature},
+};
+
+/// Tags of `AccountCompressionEvent::ApplicationData(ApplicationDataEvent::V1(..))`,
+/// the envelope Bubblegum logs its leaf through the noop program in
+const APPLICATION_DATA_EVENT_V1: [u8; 2] = [1, 0];
+
+/// Rebuilds the leaf proof of `asset_id` on an endpoint without DAS.
+///
+/// Needs `mint_signature`, the signature of the transaction that minted the asset: its
+/// owner, delegate, nonce and hashes are read from the leaf event Bubblegum logged
+/// through the noop program, and the tree is the account the asset id derives from.
+///
+/// Plain RPC can't return arbitrary proofs, so the proof comes from the tree account,
+/// which only works when:
+///
+/// - the leaf is still the tree's last appended leaf and hasn't changed since the
+///   mint, in which case the tree's rightmost proof is used, or
+/// - the canopy caches the whole tree, so no proof nodes are needed (a leaf that
+///   changed since the mint is then rejected on-chain rather than here).
+///
+/// Anything else fails with `InvalidMetadata`; those leaves need a DAS endpoint.
+pub fn leaf_proof_from_mint(
+    config: &RpcConfig,
+    asset_id: &str,
+    mint_signature: &str,
+) -> Result<LeafProof, NifError> {
+    let asset_id = parse_pubkey(asset_id)?;
+    let signature = parse_signature(mint_signature.trim())?;
+    let client = config.client();
+    let transaction = client
+        .get_transaction_with_config(
+            &signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Json),
+                commitment: Some(client.commitment()),
+                max_supported_transaction_version: Some(0),
+            },
+        )
+        .map_err(classify_client_error)?;
+
+    let leaf = leaf_from_mint_transaction(&transaction, &asset_id)?;
+    let data = fetch_merkle_tree_data(&client, &leaf.tree)?;
+    current_leaf_proof(leaf, &data)
+}
+
+/// Finds the leaf event for `asset_id` in a mint transaction and the tree it went into.
+/// The proof and root are left empty.
+fn leaf_from_mint_transaction(
+    transaction: &EncodedConfirmedTransactionWithStatusMeta,
+    asset_id: &Pubkey,
+) -> Result<LeafProof, NifError> {
+    let not_minted = || NifError::InvalidMetadata(format!("transaction did not mint {}", asset_id));
+    let meta =
+        transaction.transaction.meta.as_ref().ok_or_else(|| {
+            NifError::SerializationError("mint transaction has no meta".to_string())
+        })?;
+
+    let inner_instructions =
+        Option::<Vec<UiInnerInstructions>>::from(meta.inner_instructions.clone())
+            .unwrap_or_default();
+    let (owner, delegate, nonce, data_hash, creator_hash) = inner_instructions
+        .iter()
+        .flat_map(|inner| &inner.instructions)
+        .filter_map(|instruction| match instruction {
+            UiInstruction::Compiled(compiled) => bs58::decode(&compiled.data).into_vec().ok(),
+            UiInstruction::Parsed(_) => None,
+        })
+        .filter_map(|data| leaf_schema_from_noop_data(&data))
+        .find_map(|schema| match schema {
+            LeafSchema::V1 {
+                id,
+                owner,
+                delegate,
+                nonce,
+                data_hash,
+                creator_hash,
+            } if id == *asset_id => Some((owner, delegate, nonce, data_hash, creator_hash)),
+            _ => None,
+        })
+        .ok_or_else(not_minted)?;
+
+    let mut account_keys = match &transaction.transaction.transaction {
+        EncodedTransaction::Json(tx) => match &tx.message {
+            UiMessage::Raw(message) => message.account_keys.clone(),
+            UiMessage::Parsed(_) => Vec::new(),
+        },
+        _ => Vec::new(),
+    };
+    if let Some(loaded) = Option::<UiLoadedAddresses>::from(meta.loaded_addresses.clone()) {
+        account_keys.extend(loaded.writable);
+        account_keys.extend(loaded.readonly);
+    }
+    let tree = account_keys
+        .iter()
+        .filter_map(|key| parse_pubkey(key).ok())
+        .find(|key| get_asset_id(key, nonce) == *asset_id)
+        .ok_or_else(not_minted)?;
+
+    Ok(LeafProof {
+        tree,
+        owner,
+        delegate,
+        root: [0; 32],
+        data_hash,
+        creator_hash,
+        nonce,
+        // Leaves are appended, so a leaf's index is the tree's mint count at the time
+        index: nonce as u32,
+        proof: Vec::new(),
+    })
+}
+
+/// Decodes the leaf schema Bubblegum logs through the noop program, if `data` is one
+fn leaf_schema_from_noop_data(data: &[u8]) -> Option<LeafSchema> {
+    let application_data =
+        Vec::<u8>::try_from_slice(data.strip_prefix(&APPLICATION_DATA_EVENT_V1)?).ok()?;
+    let event = LeafSchemaEvent::try_from_slice(&application_data).ok()?;
+    (event.event_type == BubblegumEventType::LeafSchemaEvent).then_some(event.schema)
+}
+
+/// Fills in the root and proof of `leaf` from the tree's account data
+fn current_leaf_proof(mut leaf: LeafProof, data: &[u8]) -> Result<LeafProof, NifError> {
+    let rightmost = rightmost_leaf_from_account_data(data)?;
+    leaf.root = *recent_roots_from_account_data(data)?
+        .first()
+        .ok_or_else(|| NifError::InvalidMetadata("merkle tree has no root yet".to_string()))?;
+
+    if rightmost.next_index == leaf.index + 1 && rightmost.leaf == leaf.leaf_hash() {
+        leaf.proof = rightmost
+            .proof
+            .into_iter()
+            .map(Pubkey::new_from_array)
+            .collect();
+        return Ok(leaf);
+    }
+    if canopy_depth_from_account_data(data)? as usize >= rightmost.proof.len() {
+        return Ok(leaf);
+    }
+    Err(NifError::InvalidMetadata(format!(
+        "leaf {} can't be proven without DAS: it is not the tree's latest leaf or has \
+         changed since it was minted",
+        leaf.index
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use borsh::BorshSerialize;
+    use mpl_bubblegum::types::Version;
+
+    fn leaf_schema(asset_id: Pubkey) -> LeafSchema {
+        LeafSchema::V1 {
+            id: asset_id,
+            owner: Pubkey::new_unique(),
+            delegate: Pubkey::new_unique(),
+            nonce: 7,
+            data_hash: [1; 32],
+            creator_hash: [2; 32],
+        }
+    }
+
+    fn noop_data(schema: &LeafSchema) -> Vec<u8> {
+        let event = LeafSchemaEvent::new(Version::V1, schema.clone(), schema.hash());
+        let mut data = APPLICATION_DATA_EVENT_V1.to_vec();
+        data.extend(event.try_to_vec().unwrap().try_to_vec().unwrap());
+        data
+    }
+
+    #[test]
+    fn test_leaf_schema_from_noop_data() {
+        let schema = leaf_schema(Pubkey::new_unique());
+        assert_eq!(
+            leaf_schema_from_noop_data(&noop_data(&schema)),
+            Some(schema)
+        );
+
+        // A change log event from account compression carries a different tag
+        let mut change_log = noop_data(&leaf_schema(Pubkey::new_unique()));
+        change_log[0] = 0;
+        assert_eq!(leaf_schema_from_noop_data(&change_log), None);
+        assert_eq!(leaf_schema_from_noop_data(&[]), None);
+    }
+
+    /// Merkle tree account data with the given rightmost leaf and one root
+    fn tree_account(
+        max_depth: usize,
+        canopy_depth: u32,
+        rightmost: &[u8; 32],
+        next_index: u32,
+    ) -> Vec<u8> {
+        let max_buffer_size = 8;
+        let change_log_size = 40 + 32 * max_depth;
+        let tree_size = 24 + max_buffer_size * change_log_size + 32 * max_depth + 40;
+        let canopy_size = ((1 << (canopy_depth + 1)) - 2) * 32;
+        let mut data = vec![0u8; 56 + tree_size + canopy_size];
+        data[0] = 1;
+        data[2..6].copy_from_slice(&(max_buffer_size as u32).to_le_bytes());
+        data[6..10].copy_from_slice(&(max_depth as u32).to_le_bytes());
+        data[56 + 16..56 + 24].copy_from_slice(&1u64.to_le_bytes());
+        data[56 + 24..56 + 56].copy_from_slice(&[4; 32]);
+
+        let proof = 56 + 24 + max_buffer_size * change_log_size;
+        data[proof..proof + 32 * max_depth].fill(3);
+        data[proof + 32 * max_depth..proof + 32 * (max_depth + 1)].copy_from_slice(rightmost);
+        let index = proof + 32 * (max_depth + 1);
+        data[index..index + 4].copy_from_slice(&next_index.to_le_bytes());
+        data
+    }
+
+    fn minted_leaf() -> LeafProof {
+        LeafProof {
+            tree: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            delegate: Pubkey::new_unique(),
+            root: [0; 32],
+            data_hash: [1; 32],
+            creator_hash: [2; 32],
+            nonce: 5,
+            index: 5,
+            proof: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_current_leaf_proof_uses_rightmost_proof() {
+        let leaf = minted_leaf();
+        let data = tree_account(3, 0, &leaf.leaf_hash(), 6);
+
+        let leaf = current_leaf_proof(leaf, &data).unwrap();
+        assert_eq!(leaf.root, [4; 32]);
+        assert_eq!(leaf.proof, vec![Pubkey::new_from_array([3; 32]); 3]);
+    }
+
+    #[test]
+    fn test_current_leaf_proof_needs_das_for_older_leaves() {
+        let leaf = minted_leaf();
+        let data = tree_account(3, 0, &leaf.leaf_hash(), 7);
+        assert!(matches!(
+            current_leaf_proof(leaf.clone(), &data),
+            Err(NifError::InvalidMetadata(_))
+        ));
+
+        // With the whole tree in the canopy no proof nodes are needed
+        let data = tree_account(3, 3, &[9; 32], 7);
+        let leaf = current_leaf_proof(leaf, &data).unwrap();
+        assert!(leaf.proof.is_empty());
+        assert_eq!(leaf.root, [4; 32]);
+    }
+}
@@ -1,4 +1,5 @@
 // use rustler::{Error, Term};
+use solana_sdk::{instruction::InstructionError, transaction::TransactionError};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -17,6 +18,149 @@ pub enum NifError {
     InvalidKeypair(String),
     #[error("Serialization error: {0}")]
     SerializationError(String),
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(String),
+    #[error("Invalid commitment level: {0}")]
+    InvalidCommitment(String),
+    #[error("Keypair mismatch: {0}")]
+    KeypairMismatch(String),
+    #[error("Account not found: {0}")]
+    AccountNotFound(String),
+    #[error("Timed out: {0}")]
+    Timeout(String),
+    #[error("Invalid network: {0}")]
+    InvalidNetwork(String),
+    #[error("Transaction not found: {0}")]
+    TransactionNotFound(String),
+    #[error("Invalid nonce account: {0}")]
+    InvalidNonceAccount(String),
+    #[error("Invalid RPC config: {0}")]
+    InvalidConfig(String),
+    #[error("Node unhealthy: {0}")]
+    NodeUnhealthy(String),
+}
+
+/// Causes in an `RpcError` message that retrying cannot fix, checked first so a
+/// program failure reported over a flaky connection still counts as permanent
+const PERMANENT_RPC_CAUSES: &[&str] = &[
+    "insufficient funds",
+    "insufficient lamports",
+    "custom program error",
+    "metadataimmutable",
+];
+
+/// Causes in an `RpcError` message that are expected to clear up on their own
+const TRANSIENT_RPC_CAUSES: &[&str] = &[
+    "429",
+    "too many requests",
+    "rate limit",
+    "blockhash not found",
+    "block height exceeded",
+    "502",
+    "503",
+    "504",
+    "error sending request",
+    "connection",
+    "not sent after",
+];
+
+impl NifError {
+    /// Whether the same call may succeed if retried later.
+    ///
+    /// Timeouts, unhealthy nodes, rate limits, connection failures and expired
+    /// blockhashes are retryable. Bad input, insufficient funds and program errors
+    /// such as `MetadataImmutable` are permanent, as is any RPC error not recognised
+    /// as transient.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            NifError::Timeout(_) | NifError::NodeUnhealthy(_) => true,
+            NifError::TransactionNotFound(_) => true,
+            NifError::RpcError(message) => {
+                let message = message.to_lowercase();
+                !PERMANENT_RPC_CAUSES
+                    .iter()
+                    .any(|cause| message.contains(cause))
+                    && TRANSIENT_RPC_CAUSES
+                        .iter()
+                        .any(|cause| message.contains(cause))
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Returned by [`decode_bubblegum_error`] for codes outside the Bubblegum range
+pub const UNKNOWN_BUBBLEGUM_ERROR: &str = "Unknown";
+
+/// Maps a Bubblegum custom program error code (6000 = 0x1770 onwards) to its variant name
+pub fn decode_bubblegum_error(code: u32) -> &'static str {
+    match code {
+        6000 => "AssetOwnerMismatch",
+        6001 => "PublicKeyMismatch",
+        6002 => "HashingMismatch",
+        6003 => "UnsupportedSchemaVersion",
+        6004 => "CreatorShareTotalMustBe100",
+        6005 => "DuplicateCreatorAddress",
+        6006 => "CreatorDidNotVerify",
+        6007 => "CreatorNotFound",
+        6008 => "NoCreatorsPresent",
+        6009 => "CreatorHashMismatch",
+        6010 => "DataHashMismatch",
+        6011 => "CreatorsTooLong",
+        6012 => "MetadataNameTooLong",
+        6013 => "MetadataSymbolTooLong",
+        6014 => "MetadataUriTooLong",
+        6015 => "MetadataBasisPointsTooHigh",
+        6016 => "TreeAuthorityIncorrect",
+        6017 => "InsufficientMintCapacity",
+        6018 => "NumericalOverflowError",
+        6019 => "IncorrectOwner",
+        6020 => "CollectionCannotBeVerifiedInThisInstruction",
+        6021 => "CollectionNotFound",
+        6022 => "AlreadyVerified",
+        6023 => "AlreadyUnverified",
+        6024 => "UpdateAuthorityIncorrect",
+        6025 => "LeafAuthorityMustSign",
+        6026 => "CollectionMustBeSized",
+        6027 => "MetadataMintMismatch",
+        6028 => "InvalidCollectionAuthority",
+        6029 => "InvalidDelegateRecord",
+        6030 => "CollectionMasterEditionAccountInvalid",
+        6031 => "CollectionMustBeAUniqueMasterEdition",
+        6032 => "UnknownExternalError",
+        6033 => "DecompressionDisabled",
+        6034 => "MissingCollectionMintAccount",
+        6035 => "MissingCollectionMetadataAccount",
+        6036 => "CollectionMismatch",
+        6037 => "MetadataImmutable",
+        6038 => "PrimarySaleCanOnlyBeFlippedToTrue",
+        6039 => "CreatorDidNotUnverify",
+        6040 => "InvalidTokenStandard",
+        6041 => "InvalidCanopySize",
+        _ => UNKNOWN_BUBBLEGUM_ERROR,
+    }
+}
+
+/// Name of the Bubblegum variant behind a transaction's custom error code, if any.
+///
+/// The code is looked up whichever program raised it, so a custom error from another
+/// program in the same transaction (e.g. account compression, whose codes also start
+/// at 6000) can be mislabelled; callers keep the raw code in their messages.
+pub fn bubblegum_error_name(error: &TransactionError) -> Option<&'static str> {
+    match error {
+        TransactionError::InstructionError(_, InstructionError::Custom(code)) => {
+            Some(decode_bubblegum_error(*code)).filter(|name| *name != UNKNOWN_BUBBLEGUM_ERROR)
+        }
+        _ => None,
+    }
+}
+
+/// Formats a transaction error with the Bubblegum variant name appended when known
+pub fn describe_transaction_error(error: &TransactionError) -> String {
+    match bubblegum_error_name(error) {
+        Some(name) => format!("{} ({})", error, name),
+        None => error.to_string(),
+    }
 }
 
 // use thiserror::Error;
@@ -37,3 +181,68 @@ pub enum NifError {
 //     #[error("Invalid keypair: {0}")]
 //     InvalidKeypair(String),
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_retryable() {
+        let retryable = [
+            NifError::Timeout("sendTransaction".to_string()),
+            NifError::NodeUnhealthy("behind by 42 slots".to_string()),
+            NifError::RpcError("HTTP status client error (429 Too Many Requests)".to_string()),
+            NifError::RpcError("Transaction simulation failed: Blockhash not found".to_string()),
+            NifError::RpcError("transaction not sent after 3 attempts: ...".to_string()),
+        ];
+        for error in retryable {
+            assert!(error.is_retryable(), "{} should be retryable", error);
+        }
+
+        let permanent = [
+            NifError::InvalidPubkey("abc".to_string()),
+            NifError::InvalidMetadata("name too long".to_string()),
+            NifError::RpcError(
+                "Transaction simulation failed: Attempt to debit an account but found no \
+                 record of a prior credit; insufficient funds"
+                    .to_string(),
+            ),
+            NifError::RpcError("custom program error: 0x17a5 (MetadataImmutable)".to_string()),
+            NifError::RpcError("something unexpected".to_string()),
+        ];
+        for error in permanent {
+            assert!(!error.is_retryable(), "{} should be permanent", error);
+        }
+    }
+
+    #[test]
+    fn test_decode_bubblegum_error() {
+        assert_eq!(decode_bubblegum_error(6000), "AssetOwnerMismatch");
+        assert_eq!(decode_bubblegum_error(0x1771), "PublicKeyMismatch");
+        assert_eq!(decode_bubblegum_error(6025), "LeafAuthorityMustSign");
+        assert_eq!(decode_bubblegum_error(6041), "InvalidCanopySize");
+        assert_eq!(decode_bubblegum_error(1), UNKNOWN_BUBBLEGUM_ERROR);
+        assert_eq!(decode_bubblegum_error(6042), UNKNOWN_BUBBLEGUM_ERROR);
+    }
+
+    #[test]
+    fn test_bubblegum_error_name() {
+        let error = TransactionError::InstructionError(1, InstructionError::Custom(6019));
+        assert_eq!(bubblegum_error_name(&error), Some("IncorrectOwner"));
+
+        let error = TransactionError::InstructionError(1, InstructionError::InvalidArgument);
+        assert_eq!(bubblegum_error_name(&error), None);
+    }
+
+    #[test]
+    fn test_describe_transaction_error() {
+        let error = TransactionError::InstructionError(0, InstructionError::Custom(0x1771));
+        assert!(describe_transaction_error(&error).ends_with("(PublicKeyMismatch)"));
+
+        let error = TransactionError::InstructionError(0, InstructionError::Custom(1));
+        assert_eq!(describe_transaction_error(&error), error.to_string());
+
+        let error = TransactionError::BlockhashNotFound;
+        assert_eq!(describe_transaction_error(&error), error.to_string());
+    }
+}
@@ -0,0 +1,317 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use serde_json::json;
+use solana_client::{
+    rpc_config::RpcSimulateTransactionConfig, rpc_request::RpcRequest, rpc_response::Response,
+};
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction,
+    instruction::Instruction,
+    message::{Message, VersionedMessage},
+    program_utils::limited_deserialize,
+    pubkey::Pubkey,
+    transaction::{Transaction, VersionedTransaction},
+};
+
+use crate::{
+    backend::RpcBackend,
+    error::NifError,
+    utils::{classify_client_error, new_rpc_client, parse_pubkey, rpc_timeout},
+};
+
+/// Percentile of recent non-zero prioritization fees used as the suggested fee
+const PRIORITY_FEE_PERCENTILE: usize = 75;
+
+/// Suggests a compute unit price (micro-lamports per CU) for a transaction that
+/// writes to `writable_pubkeys`, based on the cluster's recent prioritization fees.
+///
+/// Returns the 75th percentile of recent non-zero fees, or 0 when no recent
+/// transaction touching those accounts paid a priority fee.
+pub fn suggest_priority_fee(rpc_url: &str, writable_pubkeys: &[String]) -> Result<u64, NifError> {
+    let accounts = writable_pubkeys
+        .iter()
+        .map(|pubkey| parse_pubkey(pubkey))
+        .collect::<Result<Vec<Pubkey>, NifError>>()?;
+
+    let client = new_rpc_client(rpc_url, rpc_timeout(None));
+    let fees = client
+        .get_recent_prioritization_fees(&accounts)
+        .map_err(|e| NifError::RpcError(e.to_string()))?
+        .into_iter()
+        .map(|fee| fee.prioritization_fee)
+        .collect::<Vec<u64>>();
+
+    Ok(fee_percentile(fees, PRIORITY_FEE_PERCENTILE))
+}
+
+/// Returns the fee in lamports the cluster would charge for a serialized message.
+///
+/// `message_base64` is a base64-encoded legacy or v0 message, i.e. an unsigned
+/// transaction's message bytes. Returns `None` when the message's blockhash has expired.
+pub fn get_fee_for_message(rpc_url: &str, message_base64: &str) -> Result<Option<u64>, NifError> {
+    let message_base64 = message_base64.trim();
+    decode_message(message_base64)?;
+
+    let client = new_rpc_client(rpc_url, rpc_timeout(None));
+    let response: Response<Option<u64>> = client
+        .send(
+            RpcRequest::GetFeeForMessage,
+            json!([message_base64, client.commitment()]),
+        )
+        .map_err(classify_client_error)?;
+    Ok(response.value)
+}
+
+/// Decodes a base64 message, rejecting anything that isn't a valid legacy or v0 message
+fn decode_message(message_base64: &str) -> Result<VersionedMessage, NifError> {
+    let bytes = BASE64
+        .decode(message_base64)
+        .map_err(|e| NifError::SerializationError(format!("Base64 decode error: {}", e)))?;
+    let message: VersionedMessage = limited_deserialize(&bytes)
+        .map_err(|e| NifError::SerializationError(format!("Invalid message: {}", e)))?;
+    message
+        .sanitize()
+        .map_err(|e| NifError::SerializationError(format!("Invalid message: {}", e)))?;
+    Ok(message)
+}
+
+/// Prepends a `set_compute_unit_price` instruction when a priority fee (in
+/// micro-lamports per compute unit) is set
+pub fn prepend_compute_unit_price(instructions: &mut Vec<Instruction>, priority_fee: Option<u64>) {
+    if let Some(micro_lamports) = priority_fee {
+        instructions.insert(
+            0,
+            ComputeBudgetInstruction::set_compute_unit_price(micro_lamports),
+        );
+    }
+}
+
+/// Factor the compute unit price grows by on each resubmit, unless configured
+pub const DEFAULT_PRIORITY_FEE_ESCALATION: f64 = 1.5;
+
+/// Ceiling for an escalated compute unit price in micro-lamports, unless configured
+pub const DEFAULT_MAX_PRIORITY_FEE: u64 = 1_000_000;
+
+/// Compute unit price escalation starts from when no priority fee was configured
+const MIN_ESCALATED_PRIORITY_FEE: u64 = 1_000;
+
+/// Compute unit price for resubmit number `resubmit` (0 is the first send).
+///
+/// The first send uses `priority_fee` as is. Each resubmit multiplies it by `factor`,
+/// starting from `MIN_ESCALATED_PRIORITY_FEE` when no fee was set, and never exceeds
+/// `max_fee` (unless the configured fee is already above it).
+pub fn escalated_priority_fee(
+    priority_fee: Option<u64>,
+    factor: f64,
+    max_fee: u64,
+    resubmit: u32,
+) -> Option<u64> {
+    if resubmit == 0 {
+        return priority_fee;
+    }
+    let base = priority_fee.unwrap_or(0).max(MIN_ESCALATED_PRIORITY_FEE);
+    let escalated = base as f64 * factor.powi(resubmit.min(i32::MAX as u32) as i32);
+    let capped = if escalated >= max_fee as f64 {
+        max_fee
+    } else {
+        escalated as u64
+    };
+    Some(capped.max(priority_fee.unwrap_or(0)))
+}
+
+/// Safety margin added on top of the simulated compute units, in percent
+const COMPUTE_UNIT_MARGIN_PERCENT: u64 = 10;
+
+/// Maximum compute units a single transaction may request
+const MAX_COMPUTE_UNIT_LIMIT: u64 = 1_400_000;
+
+/// Simulates `instructions` and prepends a `set_compute_unit_limit` instruction sized
+/// to the consumed units plus a safety margin.
+///
+/// If the simulation fails or doesn't report consumed units, the instructions are left
+/// untouched and the transaction falls back to the default compute budget.
+pub fn prepend_compute_unit_limit(
+    client: &dyn RpcBackend,
+    instructions: &mut Vec<Instruction>,
+    payer: &Pubkey,
+) {
+    if let Some(limit) = estimate_compute_unit_limit(client, instructions, payer) {
+        instructions.insert(0, ComputeBudgetInstruction::set_compute_unit_limit(limit));
+    }
+}
+
+/// Simulates an unsigned transaction and returns the padded compute unit limit
+fn estimate_compute_unit_limit(
+    client: &dyn RpcBackend,
+    instructions: &[Instruction],
+    payer: &Pubkey,
+) -> Option<u32> {
+    let tx = VersionedTransaction::from(Transaction::new_unsigned(Message::new(
+        instructions,
+        Some(payer),
+    )));
+    let config = RpcSimulateTransactionConfig {
+        sig_verify: false,
+        replace_recent_blockhash: true,
+        ..RpcSimulateTransactionConfig::default()
+    };
+
+    let simulation = client.simulate_transaction_with_config(&tx, config).ok()?;
+    if simulation.err.is_some() {
+        return None;
+    }
+
+    simulation.units_consumed.map(padded_compute_unit_limit)
+}
+
+/// Adds the safety margin to `units_consumed`, capped at the per-transaction maximum
+fn padded_compute_unit_limit(units_consumed: u64) -> u32 {
+    let padded = units_consumed + units_consumed * COMPUTE_UNIT_MARGIN_PERCENT / 100;
+    padded.min(MAX_COMPUTE_UNIT_LIMIT) as u32
+}
+
+/// Returns the `percentile`-th value of the non-zero fees, or 0 if there are none
+fn fee_percentile(fees: Vec<u64>, percentile: usize) -> u64 {
+    let mut nonzero: Vec<u64> = fees.into_iter().filter(|fee| *fee > 0).collect();
+    if nonzero.is_empty() {
+        return 0;
+    }
+    nonzero.sort_unstable();
+
+    // Nearest-rank percentile
+    let rank = (percentile * nonzero.len()).div_ceil(100).max(1);
+    nonzero[rank - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::MockRpc;
+
+    #[test]
+    fn test_fee_percentile_ignores_zero_fees() {
+        let fees = vec![0, 0, 100, 200, 300, 400, 0];
+        assert_eq!(fee_percentile(fees, 75), 300);
+    }
+
+    #[test]
+    fn test_fee_percentile_empty() {
+        assert_eq!(fee_percentile(vec![], 75), 0);
+        assert_eq!(fee_percentile(vec![0, 0, 0], 75), 0);
+    }
+
+    #[test]
+    fn test_fee_percentile_single_value() {
+        assert_eq!(fee_percentile(vec![5_000], 75), 5_000);
+    }
+
+    #[test]
+    fn test_padded_compute_unit_limit() {
+        assert_eq!(padded_compute_unit_limit(100_000), 110_000);
+        assert_eq!(padded_compute_unit_limit(0), 0);
+        assert_eq!(padded_compute_unit_limit(1_390_000), 1_400_000);
+    }
+
+    #[test]
+    fn test_prepend_compute_unit_limit() {
+        let backend = MockRpc::default();
+        let memo = Instruction::new_with_bytes(Pubkey::new_unique(), b"memo", vec![]);
+        let mut instructions = vec![memo.clone()];
+        prepend_compute_unit_limit(&backend, &mut instructions, &Pubkey::new_unique());
+        assert_eq!(
+            instructions,
+            vec![
+                ComputeBudgetInstruction::set_compute_unit_limit(11_000),
+                memo.clone()
+            ]
+        );
+
+        // Without reported units the default compute budget is kept
+        let backend = MockRpc {
+            units_consumed: None,
+            ..MockRpc::default()
+        };
+        let mut instructions = vec![memo.clone()];
+        prepend_compute_unit_limit(&backend, &mut instructions, &Pubkey::new_unique());
+        assert_eq!(instructions, vec![memo]);
+    }
+
+    #[test]
+    fn test_prepend_compute_unit_price() {
+        let memo = Instruction::new_with_bytes(Pubkey::new_unique(), b"memo", vec![]);
+        let mut instructions = vec![memo.clone()];
+        prepend_compute_unit_price(&mut instructions, None);
+        assert_eq!(instructions, vec![memo.clone()]);
+
+        prepend_compute_unit_price(&mut instructions, Some(10_000));
+        assert_eq!(
+            instructions,
+            vec![
+                ComputeBudgetInstruction::set_compute_unit_price(10_000),
+                memo
+            ]
+        );
+    }
+
+    #[test]
+    fn test_escalated_priority_fee() {
+        assert_eq!(escalated_priority_fee(None, 1.5, 1_000_000, 0), None);
+        assert_eq!(
+            escalated_priority_fee(Some(10_000), 1.5, 1_000_000, 0),
+            Some(10_000)
+        );
+        assert_eq!(
+            escalated_priority_fee(Some(10_000), 1.5, 1_000_000, 1),
+            Some(15_000)
+        );
+        assert_eq!(
+            escalated_priority_fee(Some(10_000), 1.5, 1_000_000, 2),
+            Some(22_500)
+        );
+        assert_eq!(escalated_priority_fee(None, 2.0, 1_000_000, 1), Some(2_000));
+        assert_eq!(
+            escalated_priority_fee(Some(10_000), 1.5, 20_000, 5),
+            Some(20_000)
+        );
+        assert_eq!(
+            escalated_priority_fee(Some(50_000), 1.5, 20_000, 1),
+            Some(50_000)
+        );
+    }
+
+    #[test]
+    fn test_decode_message_roundtrip() {
+        let payer = Pubkey::new_unique();
+        let instruction = ComputeBudgetInstruction::set_compute_unit_limit(200_000);
+        let message = VersionedMessage::Legacy(Message::new(&[instruction], Some(&payer)));
+        let encoded = BASE64.encode(message.serialize());
+
+        assert_eq!(decode_message(&encoded).unwrap(), message);
+    }
+
+    #[test]
+    fn test_get_fee_for_message_invalid_message() {
+        let rpc_url = "https://api.devnet.solana.com";
+        for message in ["not base64!", "AQID"] {
+            let result = get_fee_for_message(rpc_url, message);
+            assert!(
+                matches!(result, Err(NifError::SerializationError(_))),
+                "{} should be rejected",
+                message
+            );
+        }
+    }
+
+    #[test]
+    fn test_suggest_priority_fee_invalid_pubkey() {
+        let result = suggest_priority_fee(
+            "https://api.devnet.solana.com",
+            &["invalid_pubkey".to_string()],
+        );
+        assert!(result.is_err(), "Should fail with invalid pubkey");
+        if let Err(NifError::InvalidPubkey(_)) = result {
+            // Success
+        } else {
+            panic!("Wrong error type");
+        }
+    }
+}
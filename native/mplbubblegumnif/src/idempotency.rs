@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+use crate::{config::RpcConfig, transaction::MintResult};
+
+/// How long a mint is remembered under its idempotency key, unless configured
+pub const DEFAULT_IDEMPOTENCY_TTL_SECS: u64 = 600;
+
+/// Recent mints per idempotency key, with the time they were submitted
+static MINTS: OnceLock<Mutex<HashMap<String, (MintResult, Instant)>>> = OnceLock::new();
+
+/// Returns the mint submitted under `config.idempotency_key` within the TTL, if any.
+///
+/// This is best-effort: the cache lives in this process only, so it doesn't survive
+/// a restart or span nodes, and two calls racing with the same key can both submit.
+pub fn cached_mint(config: &RpcConfig) -> Option<MintResult> {
+    let key = config.idempotency_key.as_ref()?;
+    let mut mints = MINTS.get_or_init(Default::default).lock().ok()?;
+    mints.retain(|_, (_, submitted)| submitted.elapsed() < config.idempotency_ttl);
+    mints.get(key).map(|(result, _)| result.clone())
+}
+
+/// Remembers `result` under `config.idempotency_key`, if one is set
+pub fn remember_mint(config: &RpcConfig, result: &MintResult) {
+    if let (Some(key), Ok(mut mints)) = (
+        &config.idempotency_key,
+        MINTS.get_or_init(Default::default).lock(),
+    ) {
+        mints.insert(key.clone(), (result.clone(), Instant::now()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::Timings;
+    use std::time::Duration;
+
+    fn mint_result(signature: &str) -> MintResult {
+        MintResult {
+            asset_id: "asset".to_string(),
+            signature: signature.to_string(),
+            slot: None,
+            confirmation_status: None,
+            explorer_url: None,
+            simulation: None,
+            duration_ms: 0,
+            timings: Timings::default(),
+        }
+    }
+
+    #[test]
+    fn test_cached_mint_by_key() {
+        let mut config = RpcConfig::new("https://api.devnet.solana.com");
+        remember_mint(&config, &mint_result("unkeyed"));
+        assert!(cached_mint(&config).is_none());
+
+        config.idempotency_key = Some("test_cached_mint_by_key".to_string());
+        assert!(cached_mint(&config).is_none());
+        remember_mint(&config, &mint_result("first"));
+        assert_eq!(cached_mint(&config).unwrap().signature, "first");
+
+        let mut other = config.clone();
+        other.idempotency_key = Some("test_cached_mint_by_key:other".to_string());
+        assert!(cached_mint(&other).is_none());
+
+        // Past the TTL the key is free again
+        config.idempotency_ttl = Duration::ZERO;
+        assert!(cached_mint(&config).is_none());
+    }
+}
@@ -0,0 +1,187 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use rustler::NifMap;
+use solana_sdk::{
+    program_utils::limited_deserialize, sanitize::Sanitize, transaction::Transaction,
+};
+
+use crate::error::NifError;
+
+/// What a serialized transaction contains, returned to Elixir as a map
+#[derive(NifMap, Debug, Clone)]
+pub struct TransactionInspection {
+    pub fee_payer: String,
+    pub recent_blockhash: String,
+    pub accounts: Vec<InspectedAccount>,
+    pub instructions: Vec<InspectedInstruction>,
+}
+
+/// An account key of the transaction, in message order
+#[derive(NifMap, Debug, Clone, PartialEq)]
+pub struct InspectedAccount {
+    pub pubkey: String,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+/// An instruction of the transaction; `accounts` are indices into the account keys
+#[derive(NifMap, Debug, Clone, PartialEq)]
+pub struct InspectedInstruction {
+    pub program_id: String,
+    pub accounts: Vec<u8>,
+}
+
+/// Decodes a base64 wire-format legacy transaction and lists its fee payer, blockhash,
+/// accounts and instructions. Nothing is fetched, so this works on a transaction that
+/// failed to land or was never sent.
+pub fn inspect_transaction(tx_base64: &str) -> Result<TransactionInspection, NifError> {
+    let tx = decode_transaction(tx_base64)?;
+    let message = &tx.message;
+    let accounts = message
+        .account_keys
+        .iter()
+        .enumerate()
+        .map(|(index, pubkey)| InspectedAccount {
+            pubkey: pubkey.to_string(),
+            is_signer: message.is_signer(index),
+            is_writable: message.is_writable(index),
+        })
+        .collect();
+    let instructions = message
+        .instructions
+        .iter()
+        .map(|instruction| InspectedInstruction {
+            program_id: instruction.program_id(&message.account_keys).to_string(),
+            accounts: instruction.accounts.clone(),
+        })
+        .collect();
+
+    Ok(TransactionInspection {
+        fee_payer: message.account_keys[0].to_string(),
+        recent_blockhash: message.recent_blockhash.to_string(),
+        accounts,
+        instructions,
+    })
+}
+
+/// Decodes and sanitizes a base64 wire-format legacy transaction
+pub fn decode_transaction(tx_base64: &str) -> Result<Transaction, NifError> {
+    let bytes = BASE64
+        .decode(tx_base64.trim())
+        .map_err(|e| NifError::SerializationError(format!("base64 decode error: {}", e)))?;
+    // Capped at the packet size, so a forged length prefix cannot force a huge allocation
+    let tx: Transaction = limited_deserialize(&bytes).map_err(|_| {
+        NifError::SerializationError("not a serialized legacy transaction".to_string())
+    })?;
+    // Rejects out-of-range indices and inconsistent headers before they are used
+    tx.sanitize()
+        .map_err(|e| NifError::SerializationError(format!("malformed transaction: {}", e)))?;
+    Ok(tx)
+}
+
+/// Encodes a legacy transaction in wire format as base64, the inverse of
+/// [`decode_transaction`]
+pub fn encode_transaction(tx: &Transaction) -> String {
+    BASE64.encode(bincode::serialize(tx).expect("transactions always serialize"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{
+        hash::Hash,
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+        signature::{Keypair, Signer},
+    };
+
+    #[test]
+    fn test_inspect_transaction() {
+        let payer = Keypair::new();
+        let program_id = Pubkey::new_unique();
+        let writable = Pubkey::new_unique();
+        let readonly = Pubkey::new_unique();
+        let instruction = Instruction::new_with_bytes(
+            program_id,
+            &[1, 2, 3],
+            vec![
+                AccountMeta::new(writable, false),
+                AccountMeta::new_readonly(readonly, false),
+            ],
+        );
+        let blockhash = Hash::new_unique();
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[&payer],
+            blockhash,
+        );
+
+        let inspection = inspect_transaction(&encode_transaction(&tx)).unwrap();
+        assert_eq!(inspection.fee_payer, payer.pubkey().to_string());
+        assert_eq!(inspection.recent_blockhash, blockhash.to_string());
+
+        let account = |pubkey: &Pubkey| {
+            inspection
+                .accounts
+                .iter()
+                .position(|account| account.pubkey == pubkey.to_string())
+                .unwrap()
+        };
+        let payer_index = account(&payer.pubkey());
+        assert!(inspection.accounts[payer_index].is_signer);
+        assert!(inspection.accounts[payer_index].is_writable);
+        assert!(inspection.accounts[account(&writable)].is_writable);
+        assert!(!inspection.accounts[account(&readonly)].is_writable);
+        assert!(!inspection.accounts[account(&readonly)].is_signer);
+
+        assert_eq!(
+            inspection.instructions,
+            vec![InspectedInstruction {
+                program_id: program_id.to_string(),
+                accounts: vec![account(&writable) as u8, account(&readonly) as u8],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_inspect_transaction_round_trips_serialized_transaction() {
+        let payer = Keypair::new();
+        let recipient = Pubkey::new_unique();
+        let instruction = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[7; 32],
+            vec![AccountMeta::new(recipient, false)],
+        );
+        let blockhash = Hash::new_unique();
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[&payer],
+            blockhash,
+        );
+        let tx_base64 = BASE64.encode(bincode::serialize(&tx).unwrap());
+
+        assert_eq!(decode_transaction(&tx_base64).unwrap(), tx);
+        assert_eq!(encode_transaction(&tx), tx_base64);
+
+        let inspection = inspect_transaction(&tx_base64).unwrap();
+        assert_eq!(inspection.fee_payer, payer.pubkey().to_string());
+        assert_eq!(inspection.recent_blockhash, blockhash.to_string());
+        assert_eq!(inspection.accounts.len(), tx.message.account_keys.len());
+        assert_eq!(inspection.instructions.len(), 1);
+    }
+
+    #[test]
+    fn test_inspect_transaction_rejects_garbage() {
+        for tx_base64 in ["not base64!", "AAAA", &BASE64.encode([1u8; 80])] {
+            assert!(
+                matches!(
+                    inspect_transaction(tx_base64),
+                    Err(NifError::SerializationError(_))
+                ),
+                "{} should be rejected",
+                tx_base64
+            );
+        }
+    }
+}
@@ -1,18 +1,75 @@
+mod backend;
+mod combine;
+mod config;
+mod das;
+mod das_fallback;
 mod error;
+mod fees;
+mod idempotency;
+mod inspect;
+#[cfg(feature = "tracing")]
+mod logger;
+mod lookup_table;
+mod memo;
+mod nonce;
+mod offchain;
+mod pubsub;
+mod signing;
 mod transaction;
+mod tree;
 mod utils;
 use crate::{
+    combine::combine_instructions,
+    config::RpcConfig,
+    das::{
+        get_asset, get_asset_batch, get_asset_proof_batch, get_assets_by_authority,
+        get_assets_by_creator, get_assets_by_group, get_assets_by_owner,
+        get_compression_signatures_for_asset, search_assets, search_filters_from_term, AssetPage,
+    },
     error::NifError,
-    transaction::{create_tree_config, mint_v1, transfer},
-    utils::serialize_metadata_to_borsh,
+    fees::{get_fee_for_message, suggest_priority_fee},
+    inspect::inspect_transaction,
+    offchain::fetch_metadata_from_uri,
+    pubsub::confirm_via_websocket,
+    signing::{finalize_transaction, prepare_transaction},
+    transaction::{
+        approve_and_transfer, batch_mint_v1, batch_transfer_by_asset_id, burn_by_asset_id,
+        create_tree_config, mint_v1, mint_v1_raw, mint_v1_with_nonce, revoke_delegate,
+        set_and_verify_collection, set_collection_size, set_decompressible_state, transfer,
+        transfer_by_asset_id, transfer_by_asset_id_with_nonce,
+    },
+    tree::{
+        get_tree_canopy_depth, {get_tree_config, is_tree_initialized, tree_remaining_capacity},
+    },
+    utils::{
+        account_exists, asset_explorer_url, base58_decode, base58_encode,
+        compute_creator_hash_from_json, compute_data_hash_from_json, confirm_transaction,
+        derive_asset_id, derive_bubblegum_signer, derive_tree_authority, explorer_url,
+        get_account_info, get_balance, get_block, get_block_height, get_cluster_version,
+        get_epoch_info, get_health, get_latest_blockhash_with_expiry, get_signature_statuses,
+        get_slot, get_transaction, keypair_to_json_array, parse_keypair_bytes, parse_pubkey,
+        pubkey_is_valid, serialize_metadata_map_to_borsh, serialize_metadata_to_borsh,
+        serialize_metadata_to_borsh_bytes, set_blockhash_cache_ttl, sign_message,
+        validate_metadata, verify_signature, BlockhashInfo,
+    },
 };
-use rustler::{Encoder, Env, Term};
+use rustler::{types::atom, Binary, Encoder, Env, LocalPid, OwnedBinary, Term};
+use serde_json::Value;
+use solana_sdk::signature::Signer;
 
 // Define atoms for Elixir interop
 mod atoms {
     rustler::atoms! {
         ok,
-        error
+        error,
+        total,
+        page,
+        limit,
+        items,
+        timeout,
+        signature,
+        duration_ms,
+        type_ = "type"
     }
 }
 
@@ -22,8 +79,73 @@ rustler::init!(
     [
         create_tree_config_nif,
         mint_v1_nif,
+        mint_v1_raw_nif,
+        mint_v1_with_nonce_nif,
+        batch_mint_v1_nif,
         transfer_nif,
-        serialize_metadata_to_borsh_nif
+        transfer_by_asset_id_nif,
+        transfer_by_asset_id_with_nonce_nif,
+        batch_transfer_by_asset_id_nif,
+        burn_by_asset_id_nif,
+        revoke_delegate_nif,
+        approve_and_transfer_nif,
+        set_decompressible_state_nif,
+        set_and_verify_collection_nif,
+        set_collection_size_nif,
+        combine_instructions_nif,
+        prepare_transaction_nif,
+        finalize_transaction_nif,
+        get_asset_nif,
+        get_asset_batch_nif,
+        get_asset_proof_batch_nif,
+        get_compression_signatures_for_asset_nif,
+        get_assets_by_owner_nif,
+        get_assets_by_group_nif,
+        get_assets_by_creator_nif,
+        get_assets_by_authority_nif,
+        search_assets_nif,
+        confirm_transaction_nif,
+        confirm_via_websocket_nif,
+        get_signature_statuses_nif,
+        get_transaction_nif,
+        get_tree_config_nif,
+        tree_remaining_capacity_nif,
+        is_tree_initialized_nif,
+        get_tree_canopy_depth_nif,
+        derive_asset_id_nif,
+        derive_tree_authority_nif,
+        derive_bubblegum_signer_nif,
+        suggest_priority_fee_nif,
+        get_fee_for_message_nif,
+        get_latest_blockhash_with_expiry_nif,
+        get_block_height_nif,
+        get_slot_nif,
+        get_block_nif,
+        get_health_nif,
+        get_epoch_info_nif,
+        get_cluster_version_nif,
+        account_exists_nif,
+        get_account_info_nif,
+        get_balance_nif,
+        serialize_metadata_to_borsh_nif,
+        serialize_metadata_to_borsh_from_map_nif,
+        serialize_metadata_to_borsh_raw_nif,
+        validate_metadata_nif,
+        fetch_metadata_from_uri_nif,
+        base58_encode_nif,
+        base58_decode_nif,
+        inspect_transaction_nif,
+        compute_data_hash_nif,
+        compute_creator_hash_nif,
+        sign_message_nif,
+        verify_signature_nif,
+        parse_keypair_bytes_nif,
+        keypair_to_json_array_nif,
+        pubkey_is_valid_nif,
+        set_blockhash_cache_ttl_nif,
+        install_logger_nif,
+        explorer_url_nif,
+        asset_explorer_url_nif
     ]
 );
 
@@ -32,85 +154,1208 @@ rustler::init!(
 fn serialize_metadata_to_borsh_nif(env: Env, metadata_json: String) -> Term {
     match serialize_metadata_to_borsh(&metadata_json) {
         Ok(borsh_data) => (atoms::ok(), borsh_data).encode(env),
-        Err(e) => (atoms::error(), e.to_string()).encode(env),
+        Err(e) => encode_error(env, &e),
     }
 }
 
-/// NIF: Creates a tree config for compressed NFTs and submits the transaction
+/// NIF: Serializes a metadata map into Borsh format without a JSON round trip
 #[rustler::nif]
-fn create_tree_config_nif(
-    env: Env,
-    rpc_url: String,
+fn serialize_metadata_to_borsh_from_map_nif<'a>(env: Env<'a>, metadata: Term<'a>) -> Term<'a> {
+    match serialize_metadata_map_to_borsh(metadata) {
+        Ok(borsh_data) => (atoms::ok(), borsh_data).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Serializes metadata JSON into raw Borsh bytes returned as an Elixir binary
+#[rustler::nif]
+fn serialize_metadata_to_borsh_raw_nif(env: Env, metadata_json: String) -> Term {
+    match serialize_metadata_to_borsh_bytes(&metadata_json) {
+        Ok(borsh_data) => (atoms::ok(), encode_binary(env, &borsh_data)).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Validates metadata JSON and returns it normalized, with defaults filled in
+#[rustler::nif]
+fn validate_metadata_nif(env: Env, metadata_json: String) -> Term {
+    match validate_metadata(&metadata_json) {
+        Ok(normalized) => (atoms::ok(), normalized).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Builds base64 Borsh metadata from the off-chain metadata JSON at a URI
+#[rustler::nif(schedule = "DirtyIo")]
+fn fetch_metadata_from_uri_nif(env: Env, uri: String) -> Term {
+    match fetch_metadata_from_uri(&uri) {
+        Ok(borsh_base64) => (atoms::ok(), borsh_base64).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Encodes a binary as a base58 string
+#[rustler::nif]
+fn base58_encode_nif<'a>(env: Env<'a>, bytes: Binary<'a>) -> Term<'a> {
+    (atoms::ok(), base58_encode(bytes.as_slice())).encode(env)
+}
+
+/// NIF: Decodes a base58 string into a binary
+#[rustler::nif]
+fn base58_decode_nif(env: Env, encoded: String) -> Term {
+    match base58_decode(&encoded) {
+        Ok(bytes) => (atoms::ok(), encode_binary(env, &bytes)).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Decodes a base64 transaction into its fee payer, blockhash, accounts and
+/// instructions (no RPC)
+#[rustler::nif]
+fn inspect_transaction_nif(env: Env, tx_base64: String) -> Term {
+    match inspect_transaction(&tx_base64) {
+        Ok(inspection) => (atoms::ok(), inspection).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Computes the base58 Bubblegum data hash of metadata JSON
+#[rustler::nif]
+fn compute_data_hash_nif(env: Env, metadata_json: String) -> Term {
+    match compute_data_hash_from_json(&metadata_json) {
+        Ok(hash) => (atoms::ok(), hash).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Computes the base58 Bubblegum creator hash of the creators in metadata JSON
+#[rustler::nif]
+fn compute_creator_hash_nif(env: Env, metadata_json: String) -> Term {
+    match compute_creator_hash_from_json(&metadata_json) {
+        Ok(hash) => (atoms::ok(), hash).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Signs an arbitrary message, returning the base58 signature
+#[rustler::nif]
+fn sign_message_nif<'a>(env: Env<'a>, secret_key: String, message: Binary<'a>) -> Term<'a> {
+    match sign_message(&secret_key, message.as_slice()) {
+        Ok(signature) => (atoms::ok(), signature).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Verifies a base58 signature over a message; malformed signatures verify as false
+#[rustler::nif]
+fn verify_signature_nif<'a>(
+    env: Env<'a>,
+    pubkey: String,
+    message: Binary<'a>,
+    signature: String,
+) -> Term<'a> {
+    match verify_signature(&pubkey, message.as_slice(), &signature) {
+        Ok(valid) => (atoms::ok(), valid).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Validates a raw 64-byte secret key binary, returning its base58 pubkey
+#[rustler::nif]
+fn parse_keypair_bytes_nif<'a>(env: Env<'a>, secret_key: Binary<'a>) -> Term<'a> {
+    match parse_keypair_bytes(secret_key.as_slice()) {
+        Ok(keypair) => (atoms::ok(), keypair.pubkey().to_string()).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Exports a secret key as a Solana CLI JSON keyfile array
+#[rustler::nif]
+fn keypair_to_json_array_nif(env: Env, secret_key: String) -> Term {
+    match keypair_to_json_array(&secret_key) {
+        Ok(json) => (atoms::ok(), json).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Returns whether a string is a valid base58 pubkey, never an error
+#[rustler::nif]
+fn pubkey_is_valid_nif(env: Env, pubkey: String) -> Term {
+    pubkey_is_valid(&pubkey).encode(env)
+}
+
+/// NIF: Sets how many seconds a fetched blockhash is reused (0 disables caching)
+#[rustler::nif]
+fn set_blockhash_cache_ttl_nif(env: Env, ttl_secs: u64) -> Term {
+    set_blockhash_cache_ttl(ttl_secs);
+    atoms::ok().encode(env)
+}
+
+/// NIF: Forwards Rust-side tracing to `pid` as `{:bubblegum_log, level, target, message}`
+/// messages. Only works in builds with the `tracing` feature.
+#[rustler::nif]
+fn install_logger_nif(env: Env, pid: LocalPid) -> Term {
+    #[cfg(feature = "tracing")]
+    let result = logger::install_logger(pid);
+    #[cfg(not(feature = "tracing"))]
+    let result = {
+        let _ = pid;
+        Err(NifError::InvalidConfig(
+            "built without the `tracing` feature".to_string(),
+        ))
+    };
+
+    match result {
+        Ok(()) => atoms::ok().encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Builds a Solana Explorer URL for a signature on the given network
+#[rustler::nif]
+fn explorer_url_nif(env: Env, signature: String, network: String) -> Term {
+    match explorer_url(&signature, &network) {
+        Ok(url) => (atoms::ok(), url).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Builds a Solana Explorer URL for an asset id on the given network
+#[rustler::nif]
+fn asset_explorer_url_nif(env: Env, asset_id: String, network: String) -> Term {
+    match asset_explorer_url(&asset_id, &network) {
+        Ok(url) => (atoms::ok(), url).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+// The NIFs below that talk to a Solana RPC node block on network round-trips, so
+// they run on the dirty IO scheduler to avoid stalling the normal BEAM schedulers.
+// Pure CPU NIFs (e.g. metadata serialization) stay on the normal scheduler.
+
+/// NIF: Creates a tree config for compressed NFTs and submits the transaction
+#[rustler::nif(schedule = "DirtyIo")]
+#[allow(clippy::too_many_arguments)]
+fn create_tree_config_nif<'a>(
+    env: Env<'a>,
+    rpc_config: Term<'a>,
     payer_pubkey: String,
     tree_creator_pubkey: String,
+    merkle_tree_pubkey: String,
     max_depth: u32,
     max_buffer_size: u32,
     payer_secret_key: String,
     tree_creator_secret_key: String,
-) -> Term {
-    match create_tree_config(
-        &rpc_url,
-        &payer_pubkey,
-        &tree_creator_pubkey,
-        max_depth,
-        max_buffer_size,
-        &payer_secret_key,
-        &tree_creator_secret_key,
-    ) {
-        Ok(signature) => (atoms::ok(), signature).encode(env),
-        Err(e) => (atoms::error(), e.to_string()).encode(env),
+    confirm: bool,
+    auto_compute_units: bool,
+    dry_run: bool,
+    extra_signer_secret_keys: Vec<String>,
+) -> Term<'a> {
+    match RpcConfig::from_term(rpc_config).and_then(|config| {
+        create_tree_config(
+            &config,
+            &payer_pubkey,
+            &tree_creator_pubkey,
+            &merkle_tree_pubkey,
+            max_depth,
+            max_buffer_size,
+            &payer_secret_key,
+            &tree_creator_secret_key,
+            confirm,
+            auto_compute_units,
+            dry_run,
+            &extra_signer_secret_keys,
+        )
+    }) {
+        Ok(result) => (atoms::ok(), result).encode(env),
+        Err(e) => encode_error(env, &e),
     }
 }
 
 /// NIF: Mints a compressed NFT and submits the transaction
-#[rustler::nif]
-fn mint_v1_nif(
-    env: Env,
-    rpc_url: String,
+#[rustler::nif(schedule = "DirtyIo")]
+#[allow(clippy::too_many_arguments)]
+fn mint_v1_nif<'a>(
+    env: Env<'a>,
+    rpc_config: Term<'a>,
     tree_pubkey: String,
     leaf_owner: String,
     leaf_delegate: String,
     metadata_borsh: String,
     payer_secret_key: String,
     leaf_owner_secret_key: String,
-) -> Term {
-    match mint_v1(
-        &rpc_url,
-        &tree_pubkey,
-        &leaf_owner,
-        &leaf_delegate,
-        &metadata_borsh,
-        &payer_secret_key,
-        &leaf_owner_secret_key,
-    ) {
-        Ok(signature) => (atoms::ok(), signature).encode(env),
-        Err(e) => (atoms::error(), e.to_string()).encode(env),
+    confirm: bool,
+    auto_compute_units: bool,
+    memo: Option<String>,
+    fee_payer_secret_key: Option<String>,
+    dry_run: bool,
+    verify_tree: bool,
+) -> Term<'a> {
+    match RpcConfig::from_term(rpc_config).and_then(|config| {
+        mint_v1(
+            &config,
+            &tree_pubkey,
+            &leaf_owner,
+            &leaf_delegate,
+            &metadata_borsh,
+            &payer_secret_key,
+            &leaf_owner_secret_key,
+            confirm,
+            auto_compute_units,
+            memo.as_deref(),
+            fee_payer_secret_key.as_deref(),
+            dry_run,
+            verify_tree,
+        )
+    }) {
+        Ok(result) => (atoms::ok(), result).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Mints a compressed NFT from raw Borsh metadata and submits the transaction
+#[rustler::nif(schedule = "DirtyIo")]
+#[allow(clippy::too_many_arguments)]
+fn mint_v1_raw_nif<'a>(
+    env: Env<'a>,
+    rpc_config: Term<'a>,
+    tree_pubkey: String,
+    leaf_owner: String,
+    leaf_delegate: String,
+    metadata: Binary<'a>,
+    payer_secret_key: String,
+    leaf_owner_secret_key: String,
+    confirm: bool,
+    auto_compute_units: bool,
+    memo: Option<String>,
+    fee_payer_secret_key: Option<String>,
+    dry_run: bool,
+    verify_tree: bool,
+) -> Term<'a> {
+    match RpcConfig::from_term(rpc_config).and_then(|config| {
+        mint_v1_raw(
+            &config,
+            &tree_pubkey,
+            &leaf_owner,
+            &leaf_delegate,
+            metadata.as_slice(),
+            &payer_secret_key,
+            &leaf_owner_secret_key,
+            confirm,
+            auto_compute_units,
+            memo.as_deref(),
+            fee_payer_secret_key.as_deref(),
+            dry_run,
+            verify_tree,
+        )
+    }) {
+        Ok(result) => (atoms::ok(), result).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Mints a compressed NFT in a durable-nonce transaction
+#[rustler::nif(schedule = "DirtyIo")]
+#[allow(clippy::too_many_arguments)]
+fn mint_v1_with_nonce_nif<'a>(
+    env: Env<'a>,
+    rpc_config: Term<'a>,
+    tree_pubkey: String,
+    leaf_owner: String,
+    leaf_delegate: String,
+    metadata_borsh: String,
+    payer_secret_key: String,
+    nonce_account: String,
+    nonce_authority_secret_key: String,
+    confirm: bool,
+    memo: Option<String>,
+) -> Term<'a> {
+    match RpcConfig::from_term(rpc_config).and_then(|config| {
+        mint_v1_with_nonce(
+            &config,
+            &tree_pubkey,
+            &leaf_owner,
+            &leaf_delegate,
+            &metadata_borsh,
+            &payer_secret_key,
+            &nonce_account,
+            &nonce_authority_secret_key,
+            confirm,
+            memo.as_deref(),
+        )
+    }) {
+        Ok(result) => (atoms::ok(), result).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Mints a batch of compressed NFTs sharing one blockhash and RPC client
+#[rustler::nif(schedule = "DirtyIo")]
+#[allow(clippy::too_many_arguments)]
+fn batch_mint_v1_nif<'a>(
+    env: Env<'a>,
+    rpc_config: Term<'a>,
+    tree_pubkey: String,
+    items: Vec<(String, String, String)>,
+    payer_secret_key: String,
+    confirm: bool,
+    auto_compute_units: bool,
+    memo: Option<String>,
+) -> Term<'a> {
+    match RpcConfig::from_term(rpc_config).and_then(|config| {
+        batch_mint_v1(
+            &config,
+            &tree_pubkey,
+            &items,
+            &payer_secret_key,
+            confirm,
+            auto_compute_units,
+            memo.as_deref(),
+        )
+    }) {
+        Ok(results) => {
+            let results: Vec<Term> = results
+                .into_iter()
+                .map(|result| match result {
+                    Ok(result) => (atoms::ok(), result).encode(env),
+                    Err(e) => encode_error(env, &e),
+                })
+                .collect();
+            (atoms::ok(), results).encode(env)
+        }
+        Err(e) => encode_error(env, &e),
     }
 }
 
 /// NIF: Transfers a compressed NFT and submits the transaction
-#[rustler::nif]
-fn transfer_nif(
-    env: Env,
-    rpc_url: String,
+#[rustler::nif(schedule = "DirtyIo")]
+#[allow(clippy::too_many_arguments)]
+fn transfer_nif<'a>(
+    env: Env<'a>,
+    rpc_config: Term<'a>,
     tree_pubkey: String,
     leaf_owner: String,
     new_leaf_owner: String,
     leaf_index: u32,
     payer_secret_key: String,
     leaf_owner_secret_key: String,
+    confirm: bool,
+    auto_compute_units: bool,
+    memo: Option<String>,
+    dry_run: bool,
+    leaf_delegate: Option<String>,
+) -> Term<'a> {
+    match RpcConfig::from_term(rpc_config).and_then(|config| {
+        transfer(
+            &config,
+            &tree_pubkey,
+            &leaf_owner,
+            &new_leaf_owner,
+            leaf_index,
+            &payer_secret_key,
+            &leaf_owner_secret_key,
+            confirm,
+            auto_compute_units,
+            memo.as_deref(),
+            dry_run,
+            leaf_delegate.as_deref(),
+        )
+    }) {
+        Ok(result) => (atoms::ok(), result).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Transfers a compressed NFT by asset id, fetching its proof via DAS
+#[rustler::nif(schedule = "DirtyIo")]
+#[allow(clippy::too_many_arguments)]
+fn transfer_by_asset_id_nif<'a>(
+    env: Env<'a>,
+    rpc_config: Term<'a>,
+    asset_id: String,
+    new_leaf_owner: String,
+    leaf_owner_secret_key: String,
+    canopy_depth: u32,
+    lookup_tables: Vec<String>,
+    confirm: bool,
+    auto_compute_units: bool,
+    memo: Option<String>,
+    check_root: bool,
+    mint_signature: Option<String>,
+) -> Term<'a> {
+    match RpcConfig::from_term(rpc_config).and_then(|config| {
+        transfer_by_asset_id(
+            &config,
+            &asset_id,
+            &new_leaf_owner,
+            &leaf_owner_secret_key,
+            canopy_depth,
+            &lookup_tables,
+            confirm,
+            auto_compute_units,
+            memo.as_deref(),
+            check_root,
+            mint_signature.as_deref(),
+        )
+    }) {
+        Ok(result) => (atoms::ok(), result).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Transfers a compressed NFT by asset id in a durable-nonce transaction
+#[rustler::nif(schedule = "DirtyIo")]
+#[allow(clippy::too_many_arguments)]
+fn transfer_by_asset_id_with_nonce_nif<'a>(
+    env: Env<'a>,
+    rpc_config: Term<'a>,
+    asset_id: String,
+    new_leaf_owner: String,
+    leaf_owner_secret_key: String,
+    nonce_account: String,
+    nonce_authority_secret_key: String,
+    canopy_depth: u32,
+    confirm: bool,
+    memo: Option<String>,
+) -> Term<'a> {
+    match RpcConfig::from_term(rpc_config).and_then(|config| {
+        transfer_by_asset_id_with_nonce(
+            &config,
+            &asset_id,
+            &new_leaf_owner,
+            &leaf_owner_secret_key,
+            &nonce_account,
+            &nonce_authority_secret_key,
+            canopy_depth,
+            confirm,
+            memo.as_deref(),
+        )
+    }) {
+        Ok(result) => (atoms::ok(), result).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Transfers a batch of compressed NFTs by asset id, one transaction per item
+#[rustler::nif(schedule = "DirtyIo")]
+fn batch_transfer_by_asset_id_nif<'a>(
+    env: Env<'a>,
+    rpc_config: Term<'a>,
+    transfers: Vec<(String, String, String)>,
+    canopy_depth: u32,
+    confirm: bool,
+    auto_compute_units: bool,
+) -> Term<'a> {
+    match RpcConfig::from_term(rpc_config).and_then(|config| {
+        batch_transfer_by_asset_id(
+            &config,
+            &transfers,
+            canopy_depth,
+            confirm,
+            auto_compute_units,
+        )
+    }) {
+        Ok(results) => {
+            let results: Vec<Term> = results
+                .into_iter()
+                .map(|result| match result {
+                    Ok(result) => (atoms::ok(), result).encode(env),
+                    Err(e) => encode_error(env, &e),
+                })
+                .collect();
+            (atoms::ok(), results).encode(env)
+        }
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Burns a compressed NFT by asset id, fetching its proof via DAS
+#[rustler::nif(schedule = "DirtyIo")]
+#[allow(clippy::too_many_arguments)]
+fn burn_by_asset_id_nif<'a>(
+    env: Env<'a>,
+    rpc_config: Term<'a>,
+    asset_id: String,
+    leaf_owner_secret_key: String,
+    canopy_depth: u32,
+    lookup_tables: Vec<String>,
+    confirm: bool,
+    auto_compute_units: bool,
+    check_root: bool,
+) -> Term<'a> {
+    match RpcConfig::from_term(rpc_config).and_then(|config| {
+        burn_by_asset_id(
+            &config,
+            &asset_id,
+            &leaf_owner_secret_key,
+            canopy_depth,
+            &lookup_tables,
+            confirm,
+            auto_compute_units,
+            check_root,
+        )
+    }) {
+        Ok(result) => (atoms::ok(), result).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Revokes the delegate of a compressed NFT, signed by its owner
+#[rustler::nif(schedule = "DirtyIo")]
+#[allow(clippy::too_many_arguments)]
+fn revoke_delegate_nif<'a>(
+    env: Env<'a>,
+    rpc_config: Term<'a>,
+    asset_id: String,
+    leaf_owner_secret_key: String,
+    canopy_depth: u32,
+    lookup_tables: Vec<String>,
+    confirm: bool,
+    auto_compute_units: bool,
+) -> Term<'a> {
+    match RpcConfig::from_term(rpc_config).and_then(|config| {
+        revoke_delegate(
+            &config,
+            &asset_id,
+            &leaf_owner_secret_key,
+            canopy_depth,
+            &lookup_tables,
+            confirm,
+            auto_compute_units,
+        )
+    }) {
+        Ok(result) => (atoms::ok(), result).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Sets a delegate and transfers a compressed NFT in one transaction, signed by
+/// its owner
+#[rustler::nif(schedule = "DirtyIo")]
+#[allow(clippy::too_many_arguments)]
+fn approve_and_transfer_nif<'a>(
+    env: Env<'a>,
+    rpc_config: Term<'a>,
+    asset_id: String,
+    new_leaf_delegate: String,
+    new_leaf_owner: String,
+    leaf_owner_secret_key: String,
+    canopy_depth: u32,
+    lookup_tables: Vec<String>,
+    confirm: bool,
+    auto_compute_units: bool,
+) -> Term<'a> {
+    match RpcConfig::from_term(rpc_config).and_then(|config| {
+        approve_and_transfer(
+            &config,
+            &asset_id,
+            &new_leaf_delegate,
+            &new_leaf_owner,
+            &leaf_owner_secret_key,
+            canopy_depth,
+            &lookup_tables,
+            confirm,
+            auto_compute_units,
+        )
+    }) {
+        Ok(result) => (atoms::ok(), result).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Enables or disables decompression for a tree, signed by its creator
+#[rustler::nif(schedule = "DirtyIo")]
+fn set_decompressible_state_nif<'a>(
+    env: Env<'a>,
+    rpc_config: Term<'a>,
+    tree_config_pubkey: String,
+    tree_creator_secret_key: String,
+    enabled: bool,
+    confirm: bool,
+    auto_compute_units: bool,
+) -> Term<'a> {
+    match RpcConfig::from_term(rpc_config).and_then(|config| {
+        set_decompressible_state(
+            &config,
+            &tree_config_pubkey,
+            &tree_creator_secret_key,
+            enabled,
+            confirm,
+            auto_compute_units,
+        )
+    }) {
+        Ok(result) => (atoms::ok(), result).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Sets and verifies a compressed NFT's collection, signed by the collection authority
+#[rustler::nif(schedule = "DirtyIo")]
+#[allow(clippy::too_many_arguments)]
+fn set_and_verify_collection_nif<'a>(
+    env: Env<'a>,
+    rpc_config: Term<'a>,
+    asset_id: String,
+    metadata_borsh: String,
+    collection_mint: String,
+    collection_metadata: String,
+    collection_edition: String,
+    collection_authority_secret_key: String,
+    canopy_depth: u32,
+    confirm: bool,
+    auto_compute_units: bool,
+) -> Term<'a> {
+    match RpcConfig::from_term(rpc_config).and_then(|config| {
+        set_and_verify_collection(
+            &config,
+            &asset_id,
+            &metadata_borsh,
+            &collection_mint,
+            &collection_metadata,
+            &collection_edition,
+            &collection_authority_secret_key,
+            canopy_depth,
+            confirm,
+            auto_compute_units,
+        )
+    }) {
+        Ok(result) => (atoms::ok(), result).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Sets the number of items a collection tracks, signed by its collection authority
+#[rustler::nif(schedule = "DirtyIo")]
+fn set_collection_size_nif<'a>(
+    env: Env<'a>,
+    rpc_config: Term<'a>,
+    collection_mint: String,
+    collection_authority_secret_key: String,
+    size: u64,
+    confirm: bool,
+    auto_compute_units: bool,
+) -> Term<'a> {
+    match RpcConfig::from_term(rpc_config).and_then(|config| {
+        set_collection_size(
+            &config,
+            &collection_mint,
+            &collection_authority_secret_key,
+            size,
+            confirm,
+            auto_compute_units,
+        )
+    }) {
+        Ok(result) => (atoms::ok(), result).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Builds a JSON list of tagged instruction specs into one transaction and submits it
+#[rustler::nif(schedule = "DirtyIo")]
+fn combine_instructions_nif<'a>(
+    env: Env<'a>,
+    rpc_config: Term<'a>,
+    instructions_json: String,
+    signer_secret_keys: Vec<String>,
+    confirm: bool,
+    auto_compute_units: bool,
+) -> Term<'a> {
+    match RpcConfig::from_term(rpc_config).and_then(|config| {
+        combine_instructions(
+            &config,
+            &instructions_json,
+            &signer_secret_keys,
+            confirm,
+            auto_compute_units,
+        )
+    }) {
+        Ok(result) => (atoms::ok(), result).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Builds instruction specs into a transaction signed by the available keys and
+/// returns it with the message bytes the remaining signers must sign
+#[rustler::nif(schedule = "DirtyIo")]
+fn prepare_transaction_nif<'a>(
+    env: Env<'a>,
+    rpc_config: Term<'a>,
+    instructions_json: String,
+    fee_payer: String,
+    signer_secret_keys: Vec<String>,
+) -> Term<'a> {
+    match RpcConfig::from_term(rpc_config).and_then(|config| {
+        prepare_transaction(&config, &instructions_json, &fee_payer, &signer_secret_keys)
+    }) {
+        Ok(prepared) => (atoms::ok(), prepared).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Installs `{pubkey, signature}` pairs into a prepared transaction and submits it
+/// once it is fully signed
+#[rustler::nif(schedule = "DirtyIo")]
+fn finalize_transaction_nif<'a>(
+    env: Env<'a>,
+    rpc_config: Term<'a>,
+    tx_base64: String,
+    signatures: Vec<(String, String)>,
+    confirm: bool,
+) -> Term<'a> {
+    match RpcConfig::from_term(rpc_config)
+        .and_then(|config| finalize_transaction(&config, &tx_base64, &signatures, confirm))
+    {
+        Ok(result) => (atoms::ok(), result).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Fetches a compressed NFT through DAS `getAsset` and returns it as a map
+#[rustler::nif(schedule = "DirtyIo")]
+fn get_asset_nif(env: Env, rpc_url: String, asset_id: String) -> Term {
+    match get_asset(&rpc_url, &asset_id) {
+        Ok(asset) => (atoms::ok(), encode_json(env, &asset)).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Fetches several assets at once, in input order with nil for missing assets
+#[rustler::nif(schedule = "DirtyIo")]
+fn get_asset_batch_nif(env: Env, rpc_url: String, asset_ids: Vec<String>) -> Term {
+    match get_asset_batch(&rpc_url, &asset_ids) {
+        Ok(assets) => {
+            let assets: Vec<Term> = assets.iter().map(|asset| encode_json(env, asset)).collect();
+            (atoms::ok(), assets).encode(env)
+        }
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Fetches several asset proofs at once as `%{asset_id => {:ok, proof} | error}`, where
+/// `error` is `{:error, reason, retryable}`
+#[rustler::nif(schedule = "DirtyIo")]
+fn get_asset_proof_batch_nif(env: Env, rpc_url: String, asset_ids: Vec<String>) -> Term {
+    match get_asset_proof_batch(&rpc_url, &asset_ids) {
+        Ok(proofs) => {
+            let proofs = proofs
+                .iter()
+                .fold(Term::map_new(env), |map, (asset_id, proof)| {
+                    let proof = match proof {
+                        Ok(proof) => (atoms::ok(), encode_json(env, proof)).encode(env),
+                        Err(e) => encode_error(env, e),
+                    };
+                    map.map_put(asset_id, proof)
+                        .expect("map_new always returns a map")
+                });
+            (atoms::ok(), proofs).encode(env)
+        }
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Lists the transactions that touched a compressed NFT as `%{signature: _, type: _}` maps
+#[rustler::nif(schedule = "DirtyIo")]
+fn get_compression_signatures_for_asset_nif(
+    env: Env,
+    rpc_url: String,
+    asset_id: String,
+    page: u32,
+    limit: u32,
 ) -> Term {
-    match transfer(
-        &rpc_url,
-        &tree_pubkey,
-        &leaf_owner,
-        &new_leaf_owner,
-        leaf_index,
-        &payer_secret_key,
-        &leaf_owner_secret_key,
-    ) {
-        Ok(signature) => (atoms::ok(), signature).encode(env),
-        Err(e) => (atoms::error(), e.to_string()).encode(env),
+    match get_compression_signatures_for_asset(&rpc_url, &asset_id, page, limit) {
+        Ok(signatures) => {
+            let signatures: Vec<Term> = signatures
+                .iter()
+                .map(|entry| {
+                    Term::map_from_pairs(
+                        env,
+                        &[
+                            (atoms::signature().encode(env), entry.signature.encode(env)),
+                            (atoms::type_().encode(env), entry.instruction.encode(env)),
+                        ],
+                    )
+                    .expect("atom keys are unique")
+                })
+                .collect();
+            (atoms::ok(), signatures).encode(env)
+        }
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Lists one page of the compressed NFTs owned by a wallet through DAS
+#[rustler::nif(schedule = "DirtyIo")]
+fn get_assets_by_owner_nif(
+    env: Env,
+    rpc_url: String,
+    owner: String,
+    page: u32,
+    limit: u32,
+) -> Term {
+    match get_assets_by_owner(&rpc_url, &owner, page, limit) {
+        Ok(assets) => (atoms::ok(), encode_asset_page(env, &assets)).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Lists one page of the compressed NFTs in a group (e.g. a collection) through DAS
+#[rustler::nif(schedule = "DirtyIo")]
+fn get_assets_by_group_nif(
+    env: Env,
+    rpc_url: String,
+    group_key: String,
+    group_value: String,
+    page: u32,
+    limit: u32,
+) -> Term {
+    match get_assets_by_group(&rpc_url, &group_key, &group_value, page, limit) {
+        Ok(assets) => (atoms::ok(), encode_asset_page(env, &assets)).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Lists one page of the assets a creator appears on through DAS
+#[rustler::nif(schedule = "DirtyIo")]
+fn get_assets_by_creator_nif(
+    env: Env,
+    rpc_url: String,
+    creator: String,
+    only_verified: bool,
+    page: u32,
+    limit: u32,
+) -> Term {
+    match get_assets_by_creator(&rpc_url, &creator, only_verified, page, limit) {
+        Ok(assets) => (atoms::ok(), encode_asset_page(env, &assets)).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Lists one page of the assets controlled by an update authority through DAS
+#[rustler::nif(schedule = "DirtyIo")]
+fn get_assets_by_authority_nif(
+    env: Env,
+    rpc_url: String,
+    authority: String,
+    page: u32,
+    limit: u32,
+) -> Term {
+    match get_assets_by_authority(&rpc_url, &authority, page, limit) {
+        Ok(assets) => (atoms::ok(), encode_asset_page(env, &assets)).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Searches assets through DAS with a map of `searchAssets` filters
+#[rustler::nif(schedule = "DirtyIo")]
+fn search_assets_nif<'a>(
+    env: Env<'a>,
+    rpc_url: String,
+    filters: Term<'a>,
+    page: u32,
+    limit: u32,
+) -> Term<'a> {
+    match search_filters_from_term(filters)
+        .and_then(|filters| search_assets(&rpc_url, filters, page, limit))
+    {
+        Ok(assets) => (atoms::ok(), encode_asset_page(env, &assets)).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Waits for a submitted transaction to reach the requested commitment
+#[rustler::nif(schedule = "DirtyIo")]
+fn confirm_transaction_nif(
+    env: Env,
+    rpc_url: String,
+    signature: String,
+    commitment: String,
+) -> Term {
+    match confirm_transaction(&rpc_url, &signature, &commitment) {
+        Ok(result) => (atoms::ok(), result).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Waits for a submitted transaction to reach the requested commitment through a
+/// websocket subscription
+#[rustler::nif(schedule = "DirtyIo")]
+fn confirm_via_websocket_nif(
+    env: Env,
+    ws_url: String,
+    signature: String,
+    commitment: String,
+    timeout_secs: u64,
+) -> Term {
+    match confirm_via_websocket(&ws_url, &signature, &commitment, timeout_secs) {
+        Ok(result) => (atoms::ok(), result).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Looks up many signature statuses at once, nil for unknown signatures
+#[rustler::nif(schedule = "DirtyIo")]
+fn get_signature_statuses_nif(env: Env, rpc_url: String, signatures: Vec<String>) -> Term {
+    match get_signature_statuses(&rpc_url, &signatures) {
+        Ok(statuses) => (atoms::ok(), statuses).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Fetches a confirmed transaction's slot, fee, logs and error
+#[rustler::nif(schedule = "DirtyIo")]
+fn get_transaction_nif(env: Env, rpc_url: String, signature: String, commitment: String) -> Term {
+    match get_transaction(&rpc_url, &signature, &commitment) {
+        Ok(transaction) => (atoms::ok(), transaction).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Fetches and decodes a Bubblegum tree config account
+#[rustler::nif(schedule = "DirtyIo")]
+fn get_tree_config_nif(env: Env, rpc_url: String, tree_config_pubkey: String) -> Term {
+    match get_tree_config(&rpc_url, &tree_config_pubkey) {
+        Ok(tree_config) => (atoms::ok(), tree_config).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Returns how many more leaves can be minted into a tree
+#[rustler::nif(schedule = "DirtyIo")]
+fn tree_remaining_capacity_nif(env: Env, rpc_url: String, tree_config_pubkey: String) -> Term {
+    match tree_remaining_capacity(&rpc_url, &tree_config_pubkey) {
+        Ok(remaining) => (atoms::ok(), remaining).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Checks whether a tree config account exists and is initialized
+#[rustler::nif(schedule = "DirtyIo")]
+fn is_tree_initialized_nif(env: Env, rpc_url: String, tree_config_pubkey: String) -> Term {
+    match is_tree_initialized(&rpc_url, &tree_config_pubkey) {
+        Ok(initialized) => (atoms::ok(), initialized).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Reads a merkle tree's canopy depth from its account size
+#[rustler::nif(schedule = "DirtyIo")]
+fn get_tree_canopy_depth_nif(env: Env, rpc_url: String, merkle_tree_pubkey: String) -> Term {
+    match get_tree_canopy_depth(&rpc_url, &merkle_tree_pubkey) {
+        Ok(canopy_depth) => (atoms::ok(), canopy_depth).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Derives the asset id of a leaf from its tree and nonce (no RPC)
+#[rustler::nif]
+fn derive_asset_id_nif(env: Env, tree_pubkey: String, nonce: u64) -> Term {
+    match derive_asset_id(&tree_pubkey, nonce) {
+        Ok(asset_id) => (atoms::ok(), asset_id).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Derives the tree authority (tree config) PDA of a merkle tree
+#[rustler::nif]
+fn derive_tree_authority_nif(env: Env, merkle_tree: String) -> Term {
+    match parse_pubkey(&merkle_tree) {
+        Ok(tree) => (atoms::ok(), derive_tree_authority(&tree).to_string()).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Derives the Bubblegum signer PDA used for collection CPIs
+#[rustler::nif]
+fn derive_bubblegum_signer_nif(env: Env) -> Term {
+    (atoms::ok(), derive_bubblegum_signer().to_string()).encode(env)
+}
+
+/// NIF: Suggests a priority fee in micro-lamports per compute unit
+#[rustler::nif(schedule = "DirtyIo")]
+fn suggest_priority_fee_nif(env: Env, rpc_url: String, writable_pubkeys: Vec<String>) -> Term {
+    match suggest_priority_fee(&rpc_url, &writable_pubkeys) {
+        Ok(fee) => (atoms::ok(), fee).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Returns the lamport fee for a base64 message (nil if its blockhash has expired)
+#[rustler::nif(schedule = "DirtyIo")]
+fn get_fee_for_message_nif(env: Env, rpc_url: String, message_base64: String) -> Term {
+    match get_fee_for_message(&rpc_url, &message_base64) {
+        Ok(fee) => (atoms::ok(), fee).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Fetches the latest blockhash with the last block height it stays valid for
+#[rustler::nif(schedule = "DirtyIo")]
+fn get_latest_blockhash_with_expiry_nif(env: Env, rpc_url: String) -> Term {
+    match get_latest_blockhash_with_expiry(&rpc_url) {
+        Ok((blockhash, last_valid_block_height)) => {
+            let info = BlockhashInfo {
+                blockhash: blockhash.to_string(),
+                last_valid_block_height,
+            };
+            (atoms::ok(), info).encode(env)
+        }
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Returns the current block height at the given commitment
+#[rustler::nif(schedule = "DirtyIo")]
+fn get_block_height_nif(env: Env, rpc_url: String, commitment: String) -> Term {
+    match get_block_height(&rpc_url, &commitment) {
+        Ok(block_height) => (atoms::ok(), block_height).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Returns the slot the node has reached at the given commitment
+#[rustler::nif(schedule = "DirtyIo")]
+fn get_slot_nif(env: Env, rpc_url: String, commitment: String) -> Term {
+    match get_slot(&rpc_url, &commitment) {
+        Ok(slot) => (atoms::ok(), slot).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Returns a block's blockhash, parent slot and transaction signatures
+#[rustler::nif(schedule = "DirtyIo")]
+fn get_block_nif(env: Env, rpc_url: String, slot: u64) -> Term {
+    match get_block(&rpc_url, slot) {
+        Ok(block) => (atoms::ok(), block).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Health-checks an RPC endpoint, returning :ok or why the node is unhealthy
+#[rustler::nif(schedule = "DirtyIo")]
+fn get_health_nif(env: Env, rpc_url: String) -> Term {
+    match get_health(&rpc_url) {
+        Ok(()) => atoms::ok().encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Returns the cluster's current epoch, slot and block height
+#[rustler::nif(schedule = "DirtyIo")]
+fn get_epoch_info_nif(env: Env, rpc_url: String) -> Term {
+    match get_epoch_info(&rpc_url) {
+        Ok(epoch_info) => (atoms::ok(), epoch_info).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Returns the software version and feature set of the node behind an endpoint
+#[rustler::nif(schedule = "DirtyIo")]
+fn get_cluster_version_nif(env: Env, rpc_url: String) -> Term {
+    match get_cluster_version(&rpc_url) {
+        Ok(version) => (atoms::ok(), version).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Checks whether an account exists on-chain
+#[rustler::nif(schedule = "DirtyIo")]
+fn account_exists_nif(env: Env, rpc_url: String, pubkey: String) -> Term {
+    match account_exists(&rpc_url, &pubkey) {
+        Ok(exists) => (atoms::ok(), exists).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Fetches an account's owner, lamports, data length and executable flag (nil if missing)
+#[rustler::nif(schedule = "DirtyIo")]
+fn get_account_info_nif(env: Env, rpc_url: String, pubkey: String) -> Term {
+    match get_account_info(&rpc_url, &pubkey) {
+        Ok(info) => (atoms::ok(), info).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// NIF: Returns an account's balance in lamports (0 if it does not exist)
+#[rustler::nif(schedule = "DirtyIo")]
+fn get_balance_nif(env: Env, rpc_url: String, pubkey: String) -> Term {
+    match get_balance(&rpc_url, &pubkey) {
+        Ok(lamports) => (atoms::ok(), lamports).encode(env),
+        Err(e) => encode_error(env, &e),
+    }
+}
+
+/// Encodes a `NifError` as `{:error, reason, retryable}`.
+///
+/// `retryable` is [`NifError::is_retryable`], so queue consumers can decide between
+/// retrying and dropping a job without parsing `reason`. Timeouts are tagged as
+/// `{:timeout, reason}`, while every other error keeps the plain message.
+fn encode_error<'a>(env: Env<'a>, error: &NifError) -> Term<'a> {
+    let retryable = error.is_retryable();
+    match error {
+        NifError::Timeout(_) => (
+            atoms::error(),
+            (atoms::timeout(), error.to_string()),
+            retryable,
+        )
+            .encode(env),
+        _ => (atoms::error(), error.to_string(), retryable).encode(env),
+    }
+}
+
+/// Copies bytes into a new Elixir binary
+fn encode_binary<'a>(env: Env<'a>, bytes: &[u8]) -> Term<'a> {
+    let mut binary = OwnedBinary::new(bytes.len()).expect("failed to allocate binary");
+    binary.as_mut_slice().copy_from_slice(bytes);
+    binary.release(env).encode(env)
+}
+
+/// Encodes an asset page as `%{total: _, page: _, limit: _, items: [...], duration_ms: _}`
+fn encode_asset_page<'a>(env: Env<'a>, assets: &AssetPage) -> Term<'a> {
+    let items: Vec<Term> = assets
+        .items
+        .iter()
+        .map(|item| encode_json(env, item))
+        .collect();
+    Term::map_from_pairs(
+        env,
+        &[
+            (atoms::total().encode(env), assets.total.encode(env)),
+            (atoms::page().encode(env), assets.page.encode(env)),
+            (atoms::limit().encode(env), assets.limit.encode(env)),
+            (atoms::items().encode(env), items.encode(env)),
+            (
+                atoms::duration_ms().encode(env),
+                assets.duration_ms.encode(env),
+            ),
+        ],
+    )
+    .expect("atom keys are unique")
+}
+
+/// Converts a JSON value into the equivalent Elixir term, keeping object keys as strings
+fn encode_json<'a>(env: Env<'a>, value: &Value) -> Term<'a> {
+    match value {
+        Value::Null => atom::nil().encode(env),
+        Value::Bool(b) => b.encode(env),
+        Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                i.encode(env)
+            } else if let Some(u) = n.as_u64() {
+                u.encode(env)
+            } else {
+                n.as_f64().unwrap_or_default().encode(env)
+            }
+        }
+        Value::String(s) => s.encode(env),
+        Value::Array(items) => items
+            .iter()
+            .map(|item| encode_json(env, item))
+            .collect::<Vec<Term>>()
+            .encode(env),
+        Value::Object(fields) => fields.iter().fold(Term::map_new(env), |map, (k, v)| {
+            map.map_put(k, encode_json(env, v))
+                .expect("map_new always returns a map")
+        }),
     }
 }
 
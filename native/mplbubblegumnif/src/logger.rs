@@ -0,0 +1,219 @@
+//! Forwards `tracing` events from the NIFs to an Elixir process, which hands them to
+//! Logger. Only built with the `tracing` feature.
+
+use rustler::{Encoder, LocalPid, OwnedEnv};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt::{self, Write},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc, Mutex,
+    },
+    thread,
+};
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Level, Metadata, Subscriber,
+};
+
+use crate::error::NifError;
+
+mod atoms {
+    rustler::atoms! {
+        bubblegum_log,
+        error,
+        warning,
+        info,
+        debug
+    }
+}
+
+/// One formatted event on its way to Elixir
+struct LogEntry {
+    level: Level,
+    target: String,
+    message: String,
+}
+
+thread_local! {
+    /// Spans entered on this thread, innermost last
+    static SPAN_STACK: RefCell<Vec<u64>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Subscriber that prefixes each event with the fields of its enclosing spans and
+/// queues it for a forwarding thread, since threads managed by the VM (including the
+/// dirty schedulers the NIFs run on) can't send from an `OwnedEnv`.
+struct ForwardingSubscriber {
+    sender: Mutex<mpsc::Sender<LogEntry>>,
+    next_span_id: AtomicU64,
+    spans: Mutex<HashMap<u64, SpanContext>>,
+}
+
+/// A span's name and fields, kept until its last handle is closed
+struct SpanContext {
+    context: String,
+    refs: usize,
+}
+
+/// Installs the global subscriber, sending `{:bubblegum_log, level, target, message}`
+/// to `pid` for every event. Fails if a subscriber is already installed.
+pub fn install_logger(pid: LocalPid) -> Result<(), NifError> {
+    let (sender, receiver) = mpsc::channel::<LogEntry>();
+    let subscriber = ForwardingSubscriber {
+        sender: Mutex::new(sender),
+        next_span_id: AtomicU64::new(1),
+        spans: Mutex::new(HashMap::new()),
+    };
+    tracing::subscriber::set_global_default(subscriber)
+        .map_err(|_| NifError::InvalidConfig("a logger is already installed".to_string()))?;
+
+    thread::spawn(move || {
+        let mut env = OwnedEnv::new();
+        for entry in receiver {
+            env.send_and_clear(&pid, |env| {
+                (
+                    atoms::bubblegum_log(),
+                    level_atom(entry.level),
+                    entry.target,
+                    entry.message,
+                )
+                    .encode(env)
+            });
+        }
+    });
+    Ok(())
+}
+
+impl Subscriber for ForwardingSubscriber {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        *metadata.level() <= Level::DEBUG
+    }
+
+    fn new_span(&self, attributes: &span::Attributes<'_>) -> span::Id {
+        let id = self.next_span_id.fetch_add(1, Ordering::Relaxed);
+        let mut fields = FieldWriter::default();
+        attributes.record(&mut fields);
+        let context = format!("{}{{{}}}", attributes.metadata().name(), fields.0);
+        self.spans
+            .lock()
+            .unwrap()
+            .insert(id, SpanContext { context, refs: 1 });
+        span::Id::from_u64(id)
+    }
+
+    fn record(&self, _span: &span::Id, _values: &span::Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = FieldWriter::default();
+        event.record(&mut fields);
+
+        let context = SPAN_STACK.with(|stack| {
+            let spans = self.spans.lock().unwrap();
+            stack
+                .borrow()
+                .iter()
+                .filter_map(|id| spans.get(id).map(|span| span.context.clone()))
+                .collect::<Vec<String>>()
+                .join(":")
+        });
+        let message = if context.is_empty() {
+            fields.0
+        } else {
+            format!("{}: {}", context, fields.0)
+        };
+
+        let metadata = event.metadata();
+        let _ = self.sender.lock().unwrap().send(LogEntry {
+            level: *metadata.level(),
+            target: metadata.target().to_string(),
+            message,
+        });
+    }
+
+    fn enter(&self, span: &span::Id) {
+        SPAN_STACK.with(|stack| stack.borrow_mut().push(span.into_u64()));
+    }
+
+    fn exit(&self, span: &span::Id) {
+        SPAN_STACK.with(|stack| {
+            let mut stack = stack.borrow_mut();
+            if let Some(position) = stack.iter().rposition(|id| *id == span.into_u64()) {
+                stack.remove(position);
+            }
+        });
+    }
+
+    fn clone_span(&self, span: &span::Id) -> span::Id {
+        if let Some(context) = self.spans.lock().unwrap().get_mut(&span.into_u64()) {
+            context.refs += 1;
+        }
+        span.clone()
+    }
+
+    fn try_close(&self, span: span::Id) -> bool {
+        let mut spans = self.spans.lock().unwrap();
+        let Some(context) = spans.get_mut(&span.into_u64()) else {
+            return false;
+        };
+        context.refs -= 1;
+        if context.refs > 0 {
+            return false;
+        }
+        spans.remove(&span.into_u64());
+        true
+    }
+}
+
+/// Formats event and span fields as `message key=value ...`
+#[derive(Default)]
+struct FieldWriter(String);
+
+impl Visit for FieldWriter {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if !self.0.is_empty() {
+            self.0.push(' ');
+        }
+        let _ = match field.name() {
+            "message" => write!(self.0, "{:?}", value),
+            name => write!(self.0, "{}={:?}", name, value),
+        };
+    }
+}
+
+/// Maps a tracing level to the matching Elixir Logger level
+fn level_atom(level: Level) -> rustler::Atom {
+    match level {
+        Level::ERROR => atoms::error(),
+        Level::WARN => atoms::warning(),
+        Level::INFO => atoms::info(),
+        _ => atoms::debug(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_span_context_outlives_closed_clone() {
+        let (sender, receiver) = mpsc::channel::<LogEntry>();
+        let subscriber = Arc::new(ForwardingSubscriber {
+            sender: Mutex::new(sender),
+            next_span_id: AtomicU64::new(1),
+            spans: Mutex::new(HashMap::new()),
+        });
+
+        tracing::subscriber::with_default(subscriber.clone(), || {
+            let span = tracing::info_span!("mint", tree = 1);
+            drop(span.clone());
+            span.in_scope(|| tracing::info!("sent"));
+            assert_eq!(receiver.try_recv().unwrap().message, "mint{tree=1}: sent");
+            drop(span);
+        });
+        assert!(subscriber.spans.lock().unwrap().is_empty());
+    }
+}
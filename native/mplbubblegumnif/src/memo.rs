@@ -0,0 +1,67 @@
+use solana_sdk::{instruction::Instruction, pubkey, pubkey::Pubkey};
+
+use crate::error::NifError;
+
+/// SPL Memo program (v2)
+pub const MEMO_PROGRAM_ID: Pubkey = pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+
+/// Longest memo accepted, in bytes, leaving room in the transaction for the proof
+pub const MAX_MEMO_LEN: usize = 500;
+
+/// Builds an SPL memo instruction carrying `memo` as its data
+pub fn memo_instruction(memo: &str) -> Result<Instruction, NifError> {
+    if memo.len() > MAX_MEMO_LEN {
+        return Err(NifError::InvalidMetadata(format!(
+            "memo is {} bytes, the maximum is {}",
+            memo.len(),
+            MAX_MEMO_LEN
+        )));
+    }
+    Ok(Instruction::new_with_bytes(
+        MEMO_PROGRAM_ID,
+        memo.as_bytes(),
+        vec![],
+    ))
+}
+
+/// Appends a memo instruction to `instructions` when `memo` is set
+pub fn append_memo(
+    instructions: &mut Vec<Instruction>,
+    memo: Option<&str>,
+) -> Result<(), NifError> {
+    if let Some(memo) = memo {
+        instructions.push(memo_instruction(memo)?);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memo_instruction() {
+        let instruction = memo_instruction("invoice #42").unwrap();
+        assert_eq!(instruction.program_id, MEMO_PROGRAM_ID);
+        assert_eq!(instruction.data, b"invoice #42");
+        assert!(instruction.accounts.is_empty());
+    }
+
+    #[test]
+    fn test_memo_instruction_too_long() {
+        assert!(memo_instruction(&"a".repeat(MAX_MEMO_LEN)).is_ok());
+
+        let result = memo_instruction(&"a".repeat(MAX_MEMO_LEN + 1));
+        assert!(matches!(result, Err(NifError::InvalidMetadata(_))));
+    }
+
+    #[test]
+    fn test_append_memo() {
+        let mut instructions = vec![];
+        append_memo(&mut instructions, None).unwrap();
+        assert!(instructions.is_empty());
+
+        append_memo(&mut instructions, Some("hello")).unwrap();
+        assert_eq!(instructions.len(), 1);
+    }
+}
@@ -0,0 +1,99 @@
+use solana_sdk::{
+    account::Account,
+    account_utils::StateMut,
+    hash::Hash,
+    instruction::Instruction,
+    nonce::state::{State, Versions},
+    pubkey::Pubkey,
+    system_instruction, system_program,
+};
+
+use crate::{backend::RpcBackend, error::NifError, utils::classify_client_error};
+
+/// Fetches a durable nonce account and returns the blockhash it currently stores
+pub fn fetch_nonce_blockhash(
+    client: &dyn RpcBackend,
+    nonce_account: &Pubkey,
+) -> Result<Hash, NifError> {
+    let account = client
+        .get_account(nonce_account)
+        .map_err(classify_client_error)?;
+    nonce_blockhash(&account)
+        .map_err(|e| NifError::InvalidNonceAccount(format!("{}: {}", nonce_account, e)))
+}
+
+/// Reads the stored blockhash out of a nonce account's data
+pub fn nonce_blockhash(account: &Account) -> Result<Hash, String> {
+    if account.owner != system_program::id() {
+        return Err("not owned by the system program".to_string());
+    }
+    let versions: Versions = account
+        .state()
+        .map_err(|_| "data is not a nonce account".to_string())?;
+    match versions.state() {
+        State::Initialized(data) => Ok(data.blockhash()),
+        State::Uninitialized => Err("nonce account is not initialized".to_string()),
+    }
+}
+
+/// Puts `advance_nonce_account` first, which the runtime requires to recognise a
+/// durable-nonce transaction
+pub fn prepend_advance_nonce(
+    instructions: &mut Vec<Instruction>,
+    nonce_account: &Pubkey,
+    nonce_authority: &Pubkey,
+) {
+    instructions.insert(
+        0,
+        system_instruction::advance_nonce_account(nonce_account, nonce_authority),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::nonce::state::{Data, DurableNonce};
+
+    fn nonce_account(state: State) -> Account {
+        Account::new_data(1_447_680, &Versions::new(state), &system_program::id()).unwrap()
+    }
+
+    #[test]
+    fn test_nonce_blockhash_initialized() {
+        let durable_nonce = DurableNonce::from_blockhash(&Hash::new_unique());
+        let data = Data::new(Pubkey::new_unique(), durable_nonce, 5_000);
+        let account = nonce_account(State::Initialized(data));
+
+        let result = nonce_blockhash(&account);
+        assert_eq!(result.unwrap(), *durable_nonce.as_hash());
+    }
+
+    #[test]
+    fn test_nonce_blockhash_uninitialized() {
+        let account = nonce_account(State::Uninitialized);
+        assert!(nonce_blockhash(&account).is_err());
+    }
+
+    #[test]
+    fn test_nonce_blockhash_wrong_owner() {
+        let mut account = nonce_account(State::Uninitialized);
+        account.owner = Pubkey::new_unique();
+        assert!(nonce_blockhash(&account).is_err());
+    }
+
+    #[test]
+    fn test_prepend_advance_nonce() {
+        let nonce = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let mut instructions = vec![system_instruction::transfer(
+            &authority,
+            &Pubkey::new_unique(),
+            1,
+        )];
+
+        prepend_advance_nonce(&mut instructions, &nonce, &authority);
+        assert_eq!(instructions.len(), 2);
+        assert_eq!(instructions[0].program_id, system_program::id());
+        assert_eq!(instructions[0].accounts[0].pubkey, nonce);
+    }
+}
@@ -0,0 +1,208 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use serde::Deserialize;
+
+use crate::{
+    error::NifError,
+    utils::{
+        default_is_mutable, rpc_timeout, serialize_metadata_input, CreatorInput, MetadataInput,
+    },
+};
+
+/// The parts of an off-chain (Metaplex JSON standard) metadata document that end up
+/// in a leaf's on-chain metadata
+#[derive(Deserialize)]
+struct OffChainMetadata {
+    name: String,
+    #[serde(default)]
+    symbol: String,
+    #[serde(default, alias = "sellerFeeBasisPoints")]
+    seller_fee_basis_points: u16,
+    #[serde(default)]
+    properties: OffChainProperties,
+}
+
+#[derive(Deserialize, Default)]
+struct OffChainProperties {
+    creators: Option<Vec<OffChainCreator>>,
+}
+
+#[derive(Deserialize)]
+struct OffChainCreator {
+    address: String,
+    share: u8,
+    #[serde(default)]
+    verified: bool,
+}
+
+/// Fetches the off-chain metadata JSON at `uri` and serializes the matching on-chain
+/// metadata to base64 Borsh, ready for `mint_v1`.
+///
+/// A `data:application/json;base64,...` URI is decoded in place without a network
+/// call. The URI is still stored on-chain, so it must fit in `MAX_URI_LENGTH`.
+///
+/// `name`, `symbol`, `seller_fee_basis_points` and `properties.creators` are taken
+/// from the document and `uri` is stored as the leaf's URI. Creators start
+/// unverified unless the document says otherwise.
+pub fn fetch_metadata_from_uri(uri: &str) -> Result<String, NifError> {
+    let body = match uri.strip_prefix("data:") {
+        Some(data) => decode_data_uri(data)?,
+        None => fetch_metadata_document(uri)?,
+    };
+    let metadata_input = metadata_input_from_document(uri, &body)?;
+    serialize_metadata_input(metadata_input).map(|bytes| BASE64.encode(bytes))
+}
+
+/// GETs the metadata document at `uri`, failing on non-200 responses
+fn fetch_metadata_document(uri: &str) -> Result<String, NifError> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(rpc_timeout(None))
+        .build()
+        .map_err(|e| NifError::InvalidMetadata(format!("GET {} failed: {}", uri, e)))?;
+    let response = client
+        .get(uri)
+        .send()
+        .map_err(|e| NifError::InvalidMetadata(format!("GET {} failed: {}", uri, e)))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        return Err(NifError::InvalidMetadata(format!(
+            "GET {} returned HTTP {}",
+            uri, status
+        )));
+    }
+    response
+        .text()
+        .map_err(|e| NifError::InvalidMetadata(format!("GET {} failed: {}", uri, e)))
+}
+
+/// Decodes the part of a base64 `data:` URI after the scheme, e.g.
+/// `application/json;base64,eyJuYW1lIjoi...`
+fn decode_data_uri(data: &str) -> Result<String, NifError> {
+    let (media_type, payload) = data
+        .split_once(',')
+        .ok_or_else(|| NifError::InvalidMetadata("data URI has no payload".to_string()))?;
+    let media_type = media_type.strip_suffix(";base64").ok_or_else(|| {
+        NifError::InvalidMetadata("only base64 data URIs are supported".to_string())
+    })?;
+    if !media_type.is_empty() && !media_type.starts_with("application/json") {
+        return Err(NifError::InvalidMetadata(format!(
+            "unsupported data URI media type `{}`",
+            media_type
+        )));
+    }
+
+    let bytes = BASE64
+        .decode(payload)
+        .map_err(|e| NifError::InvalidMetadata(format!("data URI base64 error: {}", e)))?;
+    String::from_utf8(bytes)
+        .map_err(|e| NifError::InvalidMetadata(format!("data URI is not UTF-8: {}", e)))
+}
+
+/// Maps an off-chain metadata document to the on-chain metadata fields
+fn metadata_input_from_document(uri: &str, body: &str) -> Result<MetadataInput, NifError> {
+    let document: OffChainMetadata = serde_json::from_str(body)
+        .map_err(|e| NifError::InvalidMetadata(format!("invalid metadata JSON: {}", e)))?;
+
+    let creators = document.properties.creators.map(|creators| {
+        creators
+            .into_iter()
+            .map(|creator| CreatorInput {
+                address: creator.address,
+                verified: creator.verified,
+                share: creator.share,
+            })
+            .collect()
+    });
+
+    Ok(MetadataInput {
+        name: document.name,
+        symbol: document.symbol,
+        uri: uri.to_string(),
+        seller_fee_basis_points: document.seller_fee_basis_points,
+        creators,
+        primary_sale_happened: false,
+        is_mutable: default_is_mutable(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URI: &str = "https://example.com/nft.json";
+
+    #[test]
+    fn test_metadata_input_from_document() {
+        let body = r#"
+        {
+            "name": "Test NFT",
+            "symbol": "TNFT",
+            "description": "ignored",
+            "image": "https://example.com/nft.png",
+            "seller_fee_basis_points": 500,
+            "properties": {
+                "files": [],
+                "creators": [
+                    { "address": "11111111111111111111111111111111", "share": 100 }
+                ]
+            }
+        }
+    "#;
+
+        let input = metadata_input_from_document(URI, body).expect("Failed to map metadata");
+        assert_eq!(input.name, "Test NFT");
+        assert_eq!(input.symbol, "TNFT");
+        assert_eq!(input.uri, URI);
+        assert_eq!(input.seller_fee_basis_points, 500);
+        let creators = input.creators.unwrap();
+        assert_eq!(creators.len(), 1);
+        assert!(!creators[0].verified);
+        assert_eq!(creators[0].share, 100);
+    }
+
+    #[test]
+    fn test_metadata_input_from_document_rejects_bad_json() {
+        for body in ["<html>not json</html>", r#"{"symbol": "TNFT"}"#] {
+            let result = metadata_input_from_document(URI, body);
+            assert!(
+                matches!(result, Err(NifError::InvalidMetadata(_))),
+                "{} should be rejected",
+                body
+            );
+        }
+    }
+
+    #[test]
+    fn test_decode_data_uri() {
+        let json = r#"{"name":"On-chain","symbol":"OC"}"#;
+        let data = format!("application/json;base64,{}", BASE64.encode(json));
+        assert_eq!(decode_data_uri(&data).unwrap(), json);
+
+        for data in [
+            "application/json;base64",
+            "application/json,{}",
+            "image/png;base64,AAAA",
+            "application/json;base64,not base64!",
+        ] {
+            assert!(
+                matches!(decode_data_uri(data), Err(NifError::InvalidMetadata(_))),
+                "{} should be rejected",
+                data
+            );
+        }
+    }
+
+    #[test]
+    fn test_fetch_metadata_from_data_uri() {
+        let json = r#"{"name":"On-chain","symbol":"OC","seller_fee_basis_points":0}"#;
+        let uri = format!("data:application/json;base64,{}", BASE64.encode(json));
+        let metadata_base64 = fetch_metadata_from_uri(&uri).expect("Failed to build metadata");
+        assert!(BASE64.decode(metadata_base64).is_ok());
+    }
+
+    #[test]
+    fn test_fetch_metadata_from_unreachable_uri() {
+        let result = fetch_metadata_from_uri("http://127.0.0.1:1/nft.json");
+        assert!(matches!(result, Err(NifError::InvalidMetadata(_))));
+    }
+}
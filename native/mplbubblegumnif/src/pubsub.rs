@@ -0,0 +1,167 @@
+use solana_client::{
+    pubsub_client::PubsubClient,
+    rpc_config::RpcSignatureSubscribeConfig,
+    rpc_response::{ProcessedSignatureResult, RpcSignatureResult},
+};
+use solana_sdk::{commitment_config::CommitmentConfig, signature::Signature};
+use std::time::{Duration, Instant};
+
+use crate::{
+    error::{describe_transaction_error, NifError},
+    utils::{
+        elapsed_ms, explorer_url, network_from_rpc_url, parse_commitment, parse_signature,
+        SubmitResult, Timings,
+    },
+};
+
+/// Waits for a submitted transaction to reach `commitment` through a
+/// `signatureSubscribe` websocket instead of polling `getSignatureStatuses`.
+///
+/// The node pushes one notification once the signature reaches the commitment, so
+/// this costs a single subscription however long confirmation takes. Waiting longer
+/// than `timeout_secs` is a `Timeout` error naming the signature; the transaction may
+/// still land, so follow up with `confirm_transaction`.
+pub fn confirm_via_websocket(
+    ws_url: &str,
+    signature: &str,
+    commitment: &str,
+    timeout_secs: u64,
+) -> Result<SubmitResult, NifError> {
+    let signature = parse_signature(signature.trim())?;
+    let commitment = parse_commitment(commitment)?;
+    let timeout = Duration::from_secs(timeout_secs);
+    let confirming = Instant::now();
+
+    let config = RpcSignatureSubscribeConfig {
+        commitment: Some(commitment),
+        enable_received_notification: Some(false),
+    };
+    let (mut subscription, receiver) =
+        PubsubClient::signature_subscribe(ws_url, &signature, Some(config))
+            .map_err(|e| NifError::RpcError(format!("websocket subscribe failed: {}", e)))?;
+
+    let notification = receiver.recv_timeout(timeout);
+    // The subscription has done its job either way; a failed unsubscribe is harmless
+    let _ = subscription.shutdown();
+
+    let response = notification.map_err(|e| {
+        if e.is_timeout() {
+            NifError::Timeout(format!(
+                "Transaction {} was not confirmed within {}s",
+                signature, timeout_secs
+            ))
+        } else {
+            NifError::RpcError(format!(
+                "websocket closed before {} was confirmed",
+                signature
+            ))
+        }
+    })?;
+
+    let mut result = submit_result_from_notification(
+        ws_url,
+        &signature,
+        commitment,
+        response.context.slot,
+        response.value,
+    )?;
+    result.duration_ms = elapsed_ms(confirming);
+    result.timings.confirm_ms = result.duration_ms;
+    Ok(result)
+}
+
+/// Turns a signature notification into the same result `confirm_transaction` gives
+fn submit_result_from_notification(
+    ws_url: &str,
+    signature: &Signature,
+    commitment: CommitmentConfig,
+    slot: u64,
+    notification: RpcSignatureResult,
+) -> Result<SubmitResult, NifError> {
+    match notification {
+        RpcSignatureResult::ProcessedSignature(ProcessedSignatureResult { err: Some(err) }) => {
+            Err(NifError::RpcError(format!(
+                "Transaction {} failed: {}",
+                signature,
+                describe_transaction_error(&err)
+            )))
+        }
+        RpcSignatureResult::ProcessedSignature(ProcessedSignatureResult { err: None }) => {
+            Ok(SubmitResult {
+                signature: signature.to_string(),
+                slot: Some(slot),
+                confirmation_status: Some(commitment.commitment.to_string()),
+                explorer_url: network_from_rpc_url(ws_url)
+                    .and_then(|network| explorer_url(&signature.to_string(), network).ok()),
+                simulation: None,
+                duration_ms: 0,
+                timings: Timings::default(),
+            })
+        }
+        RpcSignatureResult::ReceivedSignature(_) => Err(NifError::RpcError(format!(
+            "unexpected received notification for {}",
+            signature
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{instruction::InstructionError, transaction::TransactionError};
+
+    const WS_URL: &str = "wss://api.devnet.solana.com";
+
+    #[test]
+    fn test_submit_result_from_notification() {
+        let signature = Signature::new_unique();
+        let confirmed =
+            RpcSignatureResult::ProcessedSignature(ProcessedSignatureResult { err: None });
+
+        let result = submit_result_from_notification(
+            WS_URL,
+            &signature,
+            CommitmentConfig::confirmed(),
+            42,
+            confirmed,
+        )
+        .unwrap();
+        assert_eq!(result.signature, signature.to_string());
+        assert_eq!(result.slot, Some(42));
+        assert_eq!(result.confirmation_status.as_deref(), Some("confirmed"));
+        assert!(result.explorer_url.unwrap().ends_with("?cluster=devnet"));
+
+        let failed = RpcSignatureResult::ProcessedSignature(ProcessedSignatureResult {
+            err: Some(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(6001),
+            )),
+        });
+        let result = submit_result_from_notification(
+            WS_URL,
+            &signature,
+            CommitmentConfig::confirmed(),
+            42,
+            failed,
+        );
+        assert!(matches!(result, Err(NifError::RpcError(_))));
+    }
+
+    #[test]
+    fn test_confirm_via_websocket_bad_input() {
+        let signature = Signature::new_unique().to_string();
+
+        let result = confirm_via_websocket(WS_URL, "not a signature", "confirmed", 1);
+        assert!(matches!(result, Err(NifError::InvalidSignature(_))));
+
+        let result = confirm_via_websocket(WS_URL, &signature, "recent", 1);
+        assert!(matches!(result, Err(NifError::InvalidCommitment(_))));
+    }
+
+    #[test]
+    fn test_confirm_via_websocket_unreachable() {
+        let signature = Signature::new_unique().to_string();
+        let result = confirm_via_websocket("ws://127.0.0.1:1", &signature, "confirmed", 1);
+        assert!(matches!(result, Err(NifError::RpcError(_))));
+    }
+}
@@ -0,0 +1,245 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use rustler::NifMap;
+use solana_sdk::{
+    hash::Hash,
+    instruction::Instruction,
+    message::Message,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    transaction::Transaction,
+};
+
+use crate::{
+    combine::{build_instruction, parse_instruction_specs},
+    config::RpcConfig,
+    error::NifError,
+    fees::prepend_compute_unit_price,
+    inspect::{decode_transaction, encode_transaction},
+    utils::{
+        get_recent_blockhash, parse_keypair, parse_pubkey, parse_signature, submit_tx, SubmitResult,
+    },
+};
+
+/// A partially signed transaction, returned to Elixir as a map.
+///
+/// Each of `missing_signers` signs the base64 `message` bytes (e.g. with
+/// `sign_message`) and the signatures are handed to `finalize_transaction` together
+/// with `transaction`.
+#[derive(NifMap, Debug, Clone)]
+pub struct PreparedTransaction {
+    pub transaction: String,
+    pub message: String,
+    pub missing_signers: Vec<String>,
+}
+
+/// Builds the instructions in `instructions_json` (see `combine_instructions` for the
+/// format) into a transaction paid for by `fee_payer` and signs it with whichever of
+/// `signer_secret_keys` it needs.
+///
+/// Signatures the available keys can't provide are left blank and their pubkeys are
+/// listed in `missing_signers`, so a multisig tree authority can sign elsewhere. The
+/// blockhash is fetched now, so every signature has to be collected before it expires.
+pub fn prepare_transaction(
+    config: &RpcConfig,
+    instructions_json: &str,
+    fee_payer: &str,
+    signer_secret_keys: &[String],
+) -> Result<PreparedTransaction, NifError> {
+    let specs = parse_instruction_specs(instructions_json)?;
+    let fee_payer = parse_pubkey(fee_payer)?;
+    let signers = signer_secret_keys
+        .iter()
+        .map(|secret_key| parse_keypair(secret_key))
+        .collect::<Result<Vec<Keypair>, NifError>>()?;
+
+    let mut instructions = specs
+        .iter()
+        .map(|spec| build_instruction(&config.rpc_url, spec, &fee_payer))
+        .collect::<Result<Vec<Instruction>, NifError>>()?;
+    prepend_compute_unit_price(&mut instructions, config.priority_fee);
+
+    let message = Message::new(&instructions, Some(&fee_payer));
+    let recent_blockhash = get_recent_blockhash(config, false)?;
+    let mut tx = Transaction::new_unsigned(message);
+    partially_sign(&mut tx, &signers, recent_blockhash)?;
+
+    Ok(PreparedTransaction {
+        transaction: encode_transaction(&tx),
+        message: BASE64.encode(tx.message_data()),
+        missing_signers: missing_signers(&tx)
+            .iter()
+            .map(|pubkey| pubkey.to_string())
+            .collect(),
+    })
+}
+
+/// Installs `signatures` (pairs of base58 pubkey and signature) into a transaction
+/// from [`prepare_transaction`] and submits it once every signature is present.
+///
+/// Each signature is checked against the message before it's installed, so a wrong
+/// one is reported by pubkey rather than as a failed preflight.
+pub fn finalize_transaction(
+    config: &RpcConfig,
+    tx_base64: &str,
+    signatures: &[(String, String)],
+    confirm: bool,
+) -> Result<SubmitResult, NifError> {
+    let mut tx = decode_transaction(tx_base64)?;
+    add_signatures(&mut tx, signatures)?;
+
+    let missing = missing_signers(&tx);
+    if !missing.is_empty() {
+        let missing: Vec<String> = missing.iter().map(|pubkey| pubkey.to_string()).collect();
+        return Err(NifError::InvalidSignature(format!(
+            "transaction is missing signatures from {}",
+            missing.join(", ")
+        )));
+    }
+    submit_tx(config, tx, confirm)
+}
+
+/// Signs `tx` with the keys in `signers` that it requires, ignoring the rest
+fn partially_sign(
+    tx: &mut Transaction,
+    signers: &[Keypair],
+    recent_blockhash: Hash,
+) -> Result<(), NifError> {
+    let signer_keys = tx.message.signer_keys();
+    let available: Vec<&Keypair> = signers
+        .iter()
+        .filter(|signer| signer_keys.contains(&&signer.pubkey()))
+        .collect();
+    tx.try_partial_sign(&available, recent_blockhash)
+        .map_err(|e| NifError::SerializationError(e.to_string()))
+}
+
+/// Verifies each signature against the message and puts it in its signer's slot
+fn add_signatures(tx: &mut Transaction, signatures: &[(String, String)]) -> Result<(), NifError> {
+    let message_data = tx.message_data();
+    for (pubkey, signature) in signatures {
+        let pubkey = parse_pubkey(pubkey)?;
+        let signature = parse_signature(signature)?;
+        let index = tx
+            .message
+            .signer_keys()
+            .iter()
+            .position(|key| **key == pubkey)
+            .ok_or_else(|| {
+                NifError::KeypairMismatch(format!("{} is not a signer of this transaction", pubkey))
+            })?;
+        if !signature.verify(pubkey.as_ref(), &message_data) {
+            return Err(NifError::InvalidSignature(format!(
+                "signature from {} does not match the message",
+                pubkey
+            )));
+        }
+        tx.signatures[index] = signature;
+    }
+    Ok(())
+}
+
+/// Signers whose signature slot is still blank
+fn missing_signers(tx: &Transaction) -> Vec<Pubkey> {
+    tx.message
+        .signer_keys()
+        .into_iter()
+        .zip(&tx.signatures)
+        .filter(|(_, signature)| **signature == Signature::default())
+        .map(|(pubkey, _)| *pubkey)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::instruction::AccountMeta;
+
+    fn two_signer_transaction(payer: &Keypair, authority: &Keypair) -> Transaction {
+        let instruction = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[],
+            vec![AccountMeta::new_readonly(authority.pubkey(), true)],
+        );
+        Transaction::new_unsigned(Message::new(&[instruction], Some(&payer.pubkey())))
+    }
+
+    #[test]
+    fn test_partially_sign_leaves_missing_signers_blank() {
+        let payer = Keypair::new();
+        let authority = Keypair::new();
+        let mut tx = two_signer_transaction(&payer, &authority);
+
+        partially_sign(
+            &mut tx,
+            &[payer.insecure_clone(), Keypair::new()],
+            Hash::new_unique(),
+        )
+        .unwrap();
+        assert_eq!(missing_signers(&tx), vec![authority.pubkey()]);
+        assert!(tx.signatures[0].verify(payer.pubkey().as_ref(), &tx.message_data()));
+    }
+
+    #[test]
+    fn test_add_signatures_completes_transaction() {
+        let payer = Keypair::new();
+        let authority = Keypair::new();
+        let mut tx = two_signer_transaction(&payer, &authority);
+        partially_sign(&mut tx, &[payer.insecure_clone()], Hash::new_unique()).unwrap();
+
+        // Round-trip through base64 the way the transaction travels between signers
+        let mut tx = decode_transaction(&encode_transaction(&tx)).unwrap();
+        let signature = authority.sign_message(&tx.message_data());
+        add_signatures(
+            &mut tx,
+            &[(authority.pubkey().to_string(), signature.to_string())],
+        )
+        .unwrap();
+        assert!(missing_signers(&tx).is_empty());
+        assert!(tx.verify().is_ok());
+    }
+
+    #[test]
+    fn test_add_signatures_rejects_bad_signatures() {
+        let payer = Keypair::new();
+        let authority = Keypair::new();
+        let mut tx = two_signer_transaction(&payer, &authority);
+        partially_sign(&mut tx, &[payer.insecure_clone()], Hash::new_unique()).unwrap();
+
+        let stranger = Keypair::new();
+        let signature = stranger.sign_message(&tx.message_data());
+        let result = add_signatures(
+            &mut tx,
+            &[(stranger.pubkey().to_string(), signature.to_string())],
+        );
+        assert!(matches!(result, Err(NifError::KeypairMismatch(_))));
+
+        let signature = authority.sign_message(b"something else");
+        let result = add_signatures(
+            &mut tx,
+            &[(authority.pubkey().to_string(), signature.to_string())],
+        );
+        assert!(matches!(result, Err(NifError::InvalidSignature(_))));
+        assert_eq!(missing_signers(&tx), vec![authority.pubkey()]);
+    }
+
+    #[test]
+    fn test_finalize_transaction_requires_every_signature() {
+        let payer = Keypair::new();
+        let authority = Keypair::new();
+        let mut tx = two_signer_transaction(&payer, &authority);
+        partially_sign(&mut tx, &[payer.insecure_clone()], Hash::new_unique()).unwrap();
+
+        let result = finalize_transaction(
+            &RpcConfig::new("https://api.devnet.solana.com"),
+            &encode_transaction(&tx),
+            &[],
+            true,
+        );
+        match result {
+            Err(NifError::InvalidSignature(message)) => {
+                assert!(message.contains(&authority.pubkey().to_string()))
+            }
+            other => panic!("expected a missing signature error, got {:?}", other),
+        }
+    }
+}
@@ -1,136 +1,668 @@
 use mpl_bubblegum::{
-    instructions::{CreateTreeConfigBuilder, MintV1Builder, TransferBuilder},
-    types::MetadataArgs,
+    accounts::TreeConfig,
+    instructions::{
+        BurnBuilder, CreateTreeConfigBuilder, DelegateBuilder, MintV1Builder,
+        SetAndVerifyCollectionBuilder, SetDecompressibleStateBuilder, TransferBuilder,
+    },
+    types::{DecompressibleState, MetadataArgs},
+    utils::get_asset_id,
 };
+use rustler::NifMap;
 use serde_json::from_str;
 use solana_sdk::{
+    hash::Hash,
+    instruction::{AccountMeta, Instruction},
     message::Message,
+    pubkey,
     pubkey::Pubkey,
-    signature::{Keypair, Signer},
-    transaction::Transaction,
+    signature::{Keypair, Signature, Signer},
+    transaction::{Transaction, VersionedTransaction},
 };
+use std::time::Instant;
 
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 // use borsh::{BorshDeserialize, BorshSerialize};
 use borsh::BorshDeserialize;
 
 use crate::{
+    backend::RpcBackend,
+    config::RpcConfig,
+    das::{
+        ensure_asset_owner, fetch_leaf_proof, fetch_leaf_proof_batch, fetch_owned_leaf_proof,
+        is_method_not_found, LeafProof,
+    },
+    das_fallback::leaf_proof_from_mint,
     error::NifError,
+    fees::{prepend_compute_unit_limit, prepend_compute_unit_price},
+    idempotency::{cached_mint, remember_mint},
+    lookup_table::{build_versioned_transaction, fetch_lookup_tables},
+    memo::append_memo,
+    nonce::{fetch_nonce_blockhash, prepend_advance_nonce},
+    tree::{ensure_merkle_tree_account, ensure_root_is_current, fetch_tree_config},
     utils::{
-        get_recent_blockhash, parse_keypair, parse_pubkey, serialize_metadata_to_borsh, submit_tx,
+        classify_client_error, compute_data_hash, derive_bubblegum_signer, derive_tree_authority,
+        get_recent_blockhash, get_recent_blockhash_with_client, parse_keypair, parse_pubkey,
+        parse_pubkey_strict, serialize_metadata_to_borsh, simulate_tx, submit_tx,
+        submit_tx_with_client, submit_versioned_tx_with_client, submit_with_resubmits,
+        SimulationResult, SubmitResult, Timings,
     },
 };
 
+/// Creates the Bubblegum tree config for `merkle_tree_pubkey`, a tree account already
+/// allocated under the account compression program.
+///
+/// `extra_signer_secret_keys` are for setups that need signatures beyond the payer and
+/// tree creator, e.g. a separate co-signer. Every signature the transaction requires
+/// must come from one of the given keys; keys it doesn't need are left out.
+#[allow(clippy::too_many_arguments)]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(rpc_url = %config.rpc_url), err)
+)]
 pub fn create_tree_config(
-    rpc_url: &str,
+    config: &RpcConfig,
     payer_pubkey: &str,
     tree_creator_pubkey: &str,
+    merkle_tree_pubkey: &str,
     max_depth: u32,
     max_buffer_size: u32,
     payer_secret_key: &str,
     tree_creator_secret_key: &str,
-) -> Result<String, NifError> {
+    confirm: bool,
+    auto_compute_units: bool,
+    dry_run: bool,
+    extra_signer_secret_keys: &[String],
+) -> Result<SubmitResult, NifError> {
+    let started = Instant::now();
     // Parse pubkeys
-    let payer = parse_pubkey(payer_pubkey)?;
-    let tree_creator = parse_pubkey(tree_creator_pubkey)?;
+    let payer = parse_pubkey_strict(payer_pubkey)?;
+    let tree_creator = parse_pubkey_strict(tree_creator_pubkey)?;
+    let merkle_tree = parse_pubkey(merkle_tree_pubkey)?;
 
     // Parse payer secret key
     let payer_keypair = parse_keypair(payer_secret_key)?;
     let tree_creator_keypair = parse_keypair(tree_creator_secret_key)?;
-
-    // Build the instruction using mpl-bubblegum
-    let instruction = CreateTreeConfigBuilder::new()
-        .payer(payer)
-        .tree_creator(tree_creator)
-        .tree_config(payer)
-        .merkle_tree(tree_creator)
-        .max_depth(max_depth)
-        .max_buffer_size(max_buffer_size)
-        .instruction();
+    let extra_signers = extra_signer_secret_keys
+        .iter()
+        .map(|secret_key| parse_keypair(secret_key))
+        .collect::<Result<Vec<Keypair>, NifError>>()?;
+
+    let instruction = build_create_tree_config_instruction(
+        &payer,
+        &tree_creator,
+        &merkle_tree,
+        max_depth,
+        max_buffer_size,
+    );
+
+    let mut instructions = vec![instruction];
+    let client = config.client();
+    if auto_compute_units {
+        prepend_compute_unit_limit(&client, &mut instructions, &payer);
+    }
 
     // Fetch recent blockhash
-    let recent_blockhash = get_recent_blockhash(rpc_url)?;
+    let recent_blockhash = get_recent_blockhash(config, false)?;
 
     // Construct transaction
-    let message = Message::new(&[instruction], Some(&payer));
-    let mut tx = Transaction::new_unsigned(message);
-    tx.try_sign(&[&payer_keypair, &tree_creator_keypair], recent_blockhash)
-        .map_err(|e| NifError::SerializationError(e.to_string()))?;
+    let keypairs: Vec<&Keypair> = [&payer_keypair, &tree_creator_keypair]
+        .into_iter()
+        .chain(&extra_signers)
+        .collect();
+    let sign =
+        |instructions: &[Instruction], recent_blockhash: Hash| -> Result<Transaction, NifError> {
+            let message = Message::new(instructions, Some(&payer));
+            let signers = select_signers(&message, &keypairs)?;
+            let mut tx = Transaction::new_unsigned(message);
+            tx.try_sign(&signers, recent_blockhash)
+                .map_err(|e| NifError::SerializationError(e.to_string()))?;
+            Ok(tx)
+        };
+
+    if dry_run {
+        prepend_compute_unit_price(&mut instructions, config.priority_fee);
+        let tx = sign(&instructions, recent_blockhash)?;
+        return simulate_tx(config, &tx).map(|result| result.with_build_started(started));
+    }
 
     // Submit transaction
-    submit_tx(rpc_url, tx)
+    submit_with_resubmits(
+        config,
+        &client,
+        &instructions,
+        recent_blockhash,
+        sign,
+        |tx| submit_tx(config, tx, confirm),
+    )
+    .map(|result| result.with_build_started(started))
+}
+
+/// Builds a `CreateTreeConfig` instruction for an already allocated `merkle_tree`,
+/// whose `TreeConfig` lives at the tree authority PDA derived from the tree
+pub fn build_create_tree_config_instruction(
+    payer: &Pubkey,
+    tree_creator: &Pubkey,
+    merkle_tree: &Pubkey,
+    max_depth: u32,
+    max_buffer_size: u32,
+) -> Instruction {
+    CreateTreeConfigBuilder::new()
+        .payer(*payer)
+        .tree_creator(*tree_creator)
+        .tree_config(derive_tree_authority(merkle_tree))
+        .merkle_tree(*merkle_tree)
+        .max_depth(max_depth)
+        .max_buffer_size(max_buffer_size)
+        .instruction()
 }
 
+/// Picks a keypair for every signature `message` requires, in the message's order,
+/// listing every required signer that has no keypair
+fn select_signers<'a>(
+    message: &Message,
+    keypairs: &[&'a Keypair],
+) -> Result<Vec<&'a Keypair>, NifError> {
+    let num_signers = message.header.num_required_signatures as usize;
+    let mut signers = Vec::with_capacity(num_signers);
+    let mut missing = Vec::new();
+    for key in &message.account_keys[..num_signers] {
+        match keypairs.iter().find(|keypair| keypair.pubkey() == *key) {
+            Some(keypair) => signers.push(*keypair),
+            None => missing.push(key.to_string()),
+        }
+    }
+
+    if !missing.is_empty() {
+        return Err(NifError::InvalidKeypair(format!(
+            "missing secret keys for required signers: {}",
+            missing.join(", ")
+        )));
+    }
+    Ok(signers)
+}
+
+/// Result of a successful mint, returned to Elixir as a map
+#[derive(NifMap, Debug, Clone)]
+pub struct MintResult {
+    pub asset_id: String,
+    pub signature: String,
+    pub slot: Option<u64>,
+    pub confirmation_status: Option<String>,
+    pub explorer_url: Option<String>,
+    pub simulation: Option<SimulationResult>,
+    pub duration_ms: u64,
+    pub timings: Timings,
+}
+
+/// Mints a compressed NFT from base64-encoded Borsh metadata.
+///
+/// See [`mint_v1_raw`] for details on the returned asset id.
+#[allow(clippy::too_many_arguments)]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(rpc_url = %config.rpc_url), err)
+)]
 pub fn mint_v1(
-    rpc_url: &str,
+    config: &RpcConfig,
     tree_pubkey: &str,
     leaf_owner: &str,
     leaf_delegate: &str,
     metadata_borsh: &str,
     payer_secret_key: &str,
     leaf_owner_secret_key: &str,
-) -> Result<String, NifError> {
+    confirm: bool,
+    auto_compute_units: bool,
+    memo: Option<&str>,
+    fee_payer_secret_key: Option<&str>,
+    dry_run: bool,
+    verify_tree: bool,
+) -> Result<MintResult, NifError> {
+    // Decode the base64-encoded Borsh-serialized metadata
+    let metadata_bytes = decode_metadata_base64(metadata_borsh)?;
+
+    mint_v1_raw(
+        config,
+        tree_pubkey,
+        leaf_owner,
+        leaf_delegate,
+        &metadata_bytes,
+        payer_secret_key,
+        leaf_owner_secret_key,
+        confirm,
+        auto_compute_units,
+        memo,
+        fee_payer_secret_key,
+        dry_run,
+        verify_tree,
+    )
+}
+
+/// Mints a compressed NFT from raw Borsh metadata and returns its asset id alongside
+/// the submission result.
+///
+/// `payer_secret_key` is the tree creator or delegate. When `fee_payer_secret_key` is
+/// given, that keypair pays the transaction fee instead and co-signs, which lets a
+/// relayer cover fees for another minting authority.
+///
+/// The asset id is derived from the tree's `num_minted` counter read just before the
+/// mint is submitted. If another mint into the same tree lands in between, that nonce
+/// is stale and the returned asset id will belong to the other leaf.
+///
+/// With `verify_tree` set the tree account is fetched first and must be owned by the
+/// account compression program; skip it to save the extra RPC call.
+///
+/// When `config.idempotency_key` is set and a mint under that key was submitted by this
+/// process within `config.idempotency_ttl`, its result is returned and nothing is sent.
+/// That includes a mint whose confirmation timed out: it may still land, so the retry
+/// gets the signature that was sent, without a slot or confirmation status.
+#[allow(clippy::too_many_arguments)]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(rpc_url = %config.rpc_url), err)
+)]
+pub fn mint_v1_raw(
+    config: &RpcConfig,
+    tree_pubkey: &str,
+    leaf_owner: &str,
+    leaf_delegate: &str,
+    metadata_bytes: &[u8],
+    payer_secret_key: &str,
+    leaf_owner_secret_key: &str,
+    confirm: bool,
+    auto_compute_units: bool,
+    memo: Option<&str>,
+    fee_payer_secret_key: Option<&str>,
+    dry_run: bool,
+    verify_tree: bool,
+) -> Result<MintResult, NifError> {
+    if !dry_run {
+        if let Some(minted) = cached_mint(config) {
+            return Ok(minted);
+        }
+    }
+    let started = Instant::now();
     // Parse pubkeys
     let tree = parse_pubkey(tree_pubkey)?;
-    let owner = parse_pubkey(leaf_owner)?;
-    let delegate = parse_pubkey(leaf_delegate)?;
     let payer_keypair = parse_keypair(payer_secret_key)?;
-    let leaf_owner_keypair = parse_keypair(leaf_owner_secret_key)?;
+    let _leaf_owner_keypair = parse_keypair(leaf_owner_secret_key)?;
+    let fee_payer_keypair = fee_payer_secret_key.map(parse_keypair).transpose()?;
+    let fee_payer = fee_payer_keypair.as_ref().unwrap_or(&payer_keypair);
 
-    // Decode the base64-encoded Borsh-serialized metadata
-    let metadata_bytes = BASE64
+    // Build the instruction
+    let instruction = build_mint_v1_instruction(
+        tree,
+        leaf_owner,
+        leaf_delegate,
+        metadata_bytes,
+        &payer_keypair.pubkey(),
+    )?;
+
+    let mut instructions = vec![instruction];
+    append_memo(&mut instructions, memo)?;
+
+    let client = config.client();
+    if verify_tree {
+        ensure_merkle_tree_account(&client, &tree)?;
+    }
+    let asset_id = next_asset_id(&client, &tree)?;
+
+    if auto_compute_units {
+        prepend_compute_unit_limit(&client, &mut instructions, &fee_payer.pubkey());
+    }
+
+    // Fetch recent blockhash
+    let recent_blockhash = get_recent_blockhash_with_client(&client)?;
+
+    // Construct and sign transaction
+    let mut signers = vec![fee_payer];
+    if fee_payer.pubkey() != payer_keypair.pubkey() {
+        signers.push(&payer_keypair);
+    }
+    let sign =
+        |instructions: &[Instruction], recent_blockhash: Hash| -> Result<Transaction, NifError> {
+            let message = Message::new(instructions, Some(&fee_payer.pubkey()));
+            let mut tx = Transaction::new_unsigned(message);
+            tx.try_sign(&signers, recent_blockhash)
+                .map_err(|e| NifError::SerializationError(e.to_string()))?;
+            Ok(tx)
+        };
+
+    if dry_run {
+        prepend_compute_unit_price(&mut instructions, config.priority_fee);
+        let result = simulate_tx(config, &sign(&instructions, recent_blockhash)?)?;
+        return Ok(mint_result(asset_id, result.with_build_started(started)));
+    }
+
+    let mut sent = None;
+    let submitted = submit_with_resubmits(
+        config,
+        &client,
+        &instructions,
+        recent_blockhash,
+        sign,
+        |tx| {
+            sent = Some(tx.signatures[0]);
+            submit_tx_with_client(
+                &client,
+                tx,
+                confirm,
+                config.skip_preflight,
+                config.rpc_max_retries,
+                config.confirm_timeout,
+            )
+        },
+    );
+    finish_mint(
+        config,
+        asset_id,
+        submitted.map(|result| result.with_build_started(started)),
+        sent,
+    )
+}
+
+/// Pairs a submission result with the asset id it minted
+fn mint_result(asset_id: Pubkey, result: SubmitResult) -> MintResult {
+    MintResult {
+        asset_id: asset_id.to_string(),
+        signature: result.signature,
+        slot: result.slot,
+        confirmation_status: result.confirmation_status,
+        explorer_url: result.explorer_url,
+        simulation: result.simulation,
+        duration_ms: result.duration_ms,
+        timings: result.timings,
+    }
+}
+
+/// Remembers a submitted mint under the idempotency key. One that timed out waiting
+/// for confirmation is remembered by `sent`, the last signature sent for it, since it
+/// may still land; the timeout is still returned.
+fn finish_mint(
+    config: &RpcConfig,
+    asset_id: Pubkey,
+    submitted: Result<SubmitResult, NifError>,
+    sent: Option<Signature>,
+) -> Result<MintResult, NifError> {
+    let result = match (submitted, sent) {
+        (Ok(result), _) => result,
+        (Err(error @ NifError::Timeout(_)), Some(signature)) => {
+            remember_mint(
+                config,
+                &mint_result(
+                    asset_id,
+                    SubmitResult {
+                        signature: signature.to_string(),
+                        slot: None,
+                        confirmation_status: None,
+                        explorer_url: None,
+                        simulation: None,
+                        duration_ms: 0,
+                        timings: Timings::default(),
+                    },
+                ),
+            );
+            return Err(error);
+        }
+        (Err(error), _) => return Err(error),
+    };
+    let minted = mint_result(asset_id, result);
+    remember_mint(config, &minted);
+    Ok(minted)
+}
+
+/// Predicts the asset id of the next leaf minted into `tree`: the new leaf takes the
+/// tree's current mint count as its nonce
+fn next_asset_id(client: &dyn RpcBackend, tree: &Pubkey) -> Result<Pubkey, NifError> {
+    let (tree_config, _) = TreeConfig::find_pda(tree);
+    let nonce = fetch_tree_config(client, &tree_config)?.num_minted;
+    Ok(get_asset_id(tree, nonce))
+}
+
+/// Mints a compressed NFT from base64-encoded Borsh metadata in a durable-nonce
+/// transaction, which stays valid until the nonce is advanced instead of expiring
+/// with a recent blockhash.
+///
+/// The nonce authority signs alongside the payer. Compute units are not estimated
+/// here since the nonce advance has to stay the first instruction. An idempotency key
+/// works as for [`mint_v1_raw`].
+#[allow(clippy::too_many_arguments)]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(rpc_url = %config.rpc_url), err)
+)]
+pub fn mint_v1_with_nonce(
+    config: &RpcConfig,
+    tree_pubkey: &str,
+    leaf_owner: &str,
+    leaf_delegate: &str,
+    metadata_borsh: &str,
+    payer_secret_key: &str,
+    nonce_account: &str,
+    nonce_authority_secret_key: &str,
+    confirm: bool,
+    memo: Option<&str>,
+) -> Result<MintResult, NifError> {
+    if let Some(minted) = cached_mint(config) {
+        return Ok(minted);
+    }
+    let started = Instant::now();
+    let tree = parse_pubkey(tree_pubkey)?;
+    let payer_keypair = parse_keypair(payer_secret_key)?;
+    let nonce_account = parse_pubkey(nonce_account)?;
+    let nonce_authority = parse_keypair(nonce_authority_secret_key)?;
+    let metadata_bytes = decode_metadata_base64(metadata_borsh)?;
+
+    let instruction = build_mint_v1_instruction(
+        tree,
+        leaf_owner,
+        leaf_delegate,
+        &metadata_bytes,
+        &payer_keypair.pubkey(),
+    )?;
+    let mut instructions = vec![instruction];
+    append_memo(&mut instructions, memo)?;
+
+    let client = config.client();
+    let asset_id = next_asset_id(&client, &tree)?;
+
+    let tx = sign_with_nonce(
+        &client,
+        config,
+        instructions,
+        &payer_keypair,
+        &nonce_account,
+        &nonce_authority,
+    )?;
+    let sent = tx.signatures[0];
+    let submitted = submit_tx_with_client(
+        &client,
+        tx,
+        confirm,
+        config.skip_preflight,
+        config.rpc_max_retries,
+        config.confirm_timeout,
+    );
+    finish_mint(
+        config,
+        asset_id,
+        submitted.map(|result| result.with_build_started(started)),
+        Some(sent),
+    )
+}
+
+/// Mints a batch of compressed NFTs into the same tree.
+///
+/// A single RPC client and recent blockhash are shared by every transaction in the
+/// batch. Each item is `(leaf_owner, leaf_delegate, metadata_borsh)` and yields its
+/// own result, so a failing item does not abort the rest of the batch.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(rpc_url = %config.rpc_url), err)
+)]
+pub fn batch_mint_v1(
+    config: &RpcConfig,
+    tree_pubkey: &str,
+    items: &[(String, String, String)],
+    payer_secret_key: &str,
+    confirm: bool,
+    auto_compute_units: bool,
+    memo: Option<&str>,
+) -> Result<Vec<Result<SubmitResult, NifError>>, NifError> {
+    let tree = parse_pubkey(tree_pubkey)?;
+    let payer_keypair = parse_keypair(payer_secret_key)?;
+    batch_mint_with_client(
+        config,
+        &config.client(),
+        tree,
+        items,
+        &payer_keypair,
+        confirm,
+        auto_compute_units,
+        memo,
+    )
+}
+
+/// [`batch_mint_v1`] through an existing RPC backend
+#[allow(clippy::too_many_arguments)]
+fn batch_mint_with_client(
+    config: &RpcConfig,
+    client: &dyn RpcBackend,
+    tree: Pubkey,
+    items: &[(String, String, String)],
+    payer_keypair: &Keypair,
+    confirm: bool,
+    auto_compute_units: bool,
+    memo: Option<&str>,
+) -> Result<Vec<Result<SubmitResult, NifError>>, NifError> {
+    // Fetch one recent blockhash for the whole batch
+    let recent_blockhash = get_recent_blockhash_with_client(client)?;
+
+    let results = items
+        .iter()
+        .map(|(leaf_owner, leaf_delegate, metadata_borsh)| {
+            let started = Instant::now();
+            let metadata_bytes = decode_metadata_base64(metadata_borsh)?;
+            let instruction = build_mint_v1_instruction(
+                tree,
+                leaf_owner,
+                leaf_delegate,
+                &metadata_bytes,
+                &payer_keypair.pubkey(),
+            )?;
+
+            let mut instructions = vec![instruction];
+            append_memo(&mut instructions, memo)?;
+            if auto_compute_units {
+                prepend_compute_unit_limit(client, &mut instructions, &payer_keypair.pubkey());
+            }
+
+            submit_with_resubmits(
+                config,
+                client,
+                &instructions,
+                recent_blockhash,
+                |priced, recent_blockhash| {
+                    let message = Message::new(priced, Some(&payer_keypair.pubkey()));
+                    let mut tx = Transaction::new_unsigned(message);
+                    tx.try_sign(&[payer_keypair], recent_blockhash)
+                        .map_err(|e| NifError::SerializationError(e.to_string()))?;
+                    Ok(tx)
+                },
+                |tx| {
+                    submit_tx_with_client(
+                        client,
+                        tx,
+                        confirm,
+                        config.skip_preflight,
+                        config.rpc_max_retries,
+                        config.confirm_timeout,
+                    )
+                },
+            )
+            .map(|result| result.with_build_started(started))
+        })
+        .collect();
+
+    Ok(results)
+}
+
+/// Decodes base64-encoded Borsh metadata into raw bytes
+pub fn decode_metadata_base64(metadata_borsh: &str) -> Result<Vec<u8>, NifError> {
+    BASE64
         .decode(metadata_borsh)
-        .map_err(|e| NifError::InvalidMetadata(format!("Base64 decode error: {}", e)))?;
+        .map_err(|e| NifError::InvalidMetadata(format!("Base64 decode error: {}", e)))
+}
+
+/// Builds a `MintV1` instruction from base58 pubkeys and raw Borsh metadata
+pub fn build_mint_v1_instruction(
+    tree: Pubkey,
+    leaf_owner: &str,
+    leaf_delegate: &str,
+    metadata_bytes: &[u8],
+    payer: &Pubkey,
+) -> Result<Instruction, NifError> {
+    let owner = parse_pubkey(leaf_owner)?;
+    let delegate = parse_pubkey(leaf_delegate)?;
 
     // Deserialize the Borsh bytes into MetadataArgs
-    let metadata = MetadataArgs::try_from_slice(&metadata_bytes)
+    let metadata = MetadataArgs::try_from_slice(metadata_bytes)
         .map_err(|e| NifError::InvalidMetadata(format!("Borsh deserialize error: {}", e)))?;
 
-    // Build the instruction
-    let instruction = MintV1Builder::new()
-        .tree_config(tree)
+    Ok(MintV1Builder::new()
+        .tree_config(derive_tree_authority(&tree))
         .leaf_owner(owner)
         .leaf_delegate(delegate)
         .merkle_tree(tree)
-        .payer(payer_keypair.pubkey()) // Added
-        .tree_creator_or_delegate(payer_keypair.pubkey())
+        .payer(*payer) // Added
+        .tree_creator_or_delegate(*payer)
         .metadata(metadata)
-        .instruction();
-
-    // Fetch recent blockhash
-    let recent_blockhash = get_recent_blockhash(rpc_url)?;
-
-    // Construct and sign transaction
-    let message = Message::new(&[instruction], Some(&payer_keypair.pubkey()));
-    let mut tx = Transaction::new_unsigned(message);
-    tx.try_sign(&[&payer_keypair], recent_blockhash)
-        .map_err(|e| NifError::SerializationError(e.to_string()))?;
-
-    submit_tx(rpc_url, tx)
+        .instruction())
 }
 
+/// Transfers a compressed NFT to `new_leaf_owner`.
+///
+/// `leaf_owner_secret_key` may belong to either the owner or the current
+/// `leaf_delegate` (defaulting to the owner), so escrow programs holding delegate
+/// authority can move the leaf.
+#[allow(clippy::too_many_arguments)]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(rpc_url = %config.rpc_url), err)
+)]
 pub fn transfer(
-    rpc_url: &str,
+    config: &RpcConfig,
     tree_pubkey: &str,
     leaf_owner: &str,
     new_leaf_owner: &str,
     leaf_index: u32,
     payer_secret_key: &str,
     leaf_owner_secret_key: &str,
-) -> Result<String, NifError> {
+    confirm: bool,
+    auto_compute_units: bool,
+    memo: Option<&str>,
+    dry_run: bool,
+    leaf_delegate: Option<&str>,
+) -> Result<SubmitResult, NifError> {
+    let started = Instant::now();
     // Parse pubkeys
     let tree = parse_pubkey(tree_pubkey)?;
-    let owner = parse_pubkey(leaf_owner)?;
+    let owner = parse_pubkey_strict(leaf_owner)?;
     let new_owner = parse_pubkey(new_leaf_owner)?;
+    let delegate = leaf_delegate
+        .map(parse_pubkey)
+        .transpose()?
+        .unwrap_or(owner);
     let payer_keypair = parse_keypair(payer_secret_key)?;
     let leaf_owner_keypair = parse_keypair(leaf_owner_secret_key)?;
+    let (owner_is_signer, delegate_is_signer) =
+        transfer_signer_roles(&owner, &delegate, &leaf_owner_keypair.pubkey())?;
 
     // Build the instruction
     let instruction = TransferBuilder::new()
-        .tree_config(tree)
+        .tree_config(derive_tree_authority(&tree))
         .merkle_tree(tree)
-        .leaf_owner(owner, true) // check once
-        .leaf_delegate(owner, false)
+        .leaf_owner(owner, owner_is_signer)
+        .leaf_delegate(delegate, delegate_is_signer)
         .new_leaf_owner(new_owner)
         .root([0; 32]) // Placeholder
         .data_hash([0; 32]) // Placeholder
@@ -139,16 +671,973 @@ pub fn transfer(
         .index(leaf_index) // on leaf_index found in transferBuilder check once again
         .instruction();
 
+    let mut instructions = vec![instruction];
+    append_memo(&mut instructions, memo)?;
+    let client = config.client();
+    if auto_compute_units {
+        prepend_compute_unit_limit(&client, &mut instructions, &payer_keypair.pubkey());
+    }
+
     // Fetch recent blockhash
-    let recent_blockhash = get_recent_blockhash(rpc_url)?;
+    let recent_blockhash = get_recent_blockhash(config, false)?;
 
     // Construct and sign transaction
-    let message = Message::new(&[instruction], Some(&payer_keypair.pubkey()));
+    let sign =
+        |instructions: &[Instruction], recent_blockhash: Hash| -> Result<Transaction, NifError> {
+            let message = Message::new(instructions, Some(&payer_keypair.pubkey()));
+            let mut tx = Transaction::new_unsigned(message);
+            tx.try_sign(&[&payer_keypair, &leaf_owner_keypair], recent_blockhash)
+                .map_err(|e| NifError::SerializationError(e.to_string()))?;
+            Ok(tx)
+        };
+
+    if dry_run {
+        prepend_compute_unit_price(&mut instructions, config.priority_fee);
+        let tx = sign(&instructions, recent_blockhash)?;
+        return simulate_tx(config, &tx).map(|result| result.with_build_started(started));
+    }
+
+    submit_with_resubmits(
+        config,
+        &client,
+        &instructions,
+        recent_blockhash,
+        sign,
+        |tx| submit_tx(config, tx, confirm),
+    )
+    .map(|result| result.with_build_started(started))
+}
+
+/// Works out whether the owner or the delegate signs a transfer made by `signer`.
+///
+/// Exactly one of them signs; when the owner is its own delegate the owner signs.
+fn transfer_signer_roles(
+    owner: &Pubkey,
+    delegate: &Pubkey,
+    signer: &Pubkey,
+) -> Result<(bool, bool), NifError> {
+    if signer == owner {
+        Ok((true, false))
+    } else if signer == delegate {
+        Ok((false, true))
+    } else {
+        Err(NifError::KeypairMismatch(format!(
+            "signer {} is neither the leaf owner {} nor its delegate {}",
+            signer, owner, delegate
+        )))
+    }
+}
+
+/// Transfers a compressed NFT knowing only its asset id, fetching the proof via DAS.
+///
+/// When `lookup_tables` is non-empty the transfer is sent as a v0 transaction that
+/// resolves the proof accounts through those tables, which deep trees need to stay
+/// under the legacy account limit.
+///
+/// With `check_root` set the proof's root is checked against the tree account first,
+/// so a stale proof fails with a clear error instead of on-chain.
+///
+/// If the endpoint doesn't support DAS and `mint_signature` (the signature of the
+/// transaction that minted the asset) is given, the leaf is rebuilt from that
+/// transaction and the tree account instead; see `leaf_proof_from_mint` for the
+/// leaves this can prove.
+#[allow(clippy::too_many_arguments)]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(rpc_url = %config.rpc_url), err)
+)]
+pub fn transfer_by_asset_id(
+    config: &RpcConfig,
+    asset_id: &str,
+    new_leaf_owner: &str,
+    leaf_owner_secret_key: &str,
+    canopy_depth: u32,
+    lookup_tables: &[String],
+    confirm: bool,
+    auto_compute_units: bool,
+    memo: Option<&str>,
+    check_root: bool,
+    mint_signature: Option<&str>,
+) -> Result<SubmitResult, NifError> {
+    let started = Instant::now();
+    let new_owner = parse_pubkey(new_leaf_owner)?;
+    let owner_keypair = parse_keypair(leaf_owner_secret_key)?;
+    let leaf = match (
+        fetch_owned_leaf_proof(&config.rpc_url, asset_id, &owner_keypair.pubkey()),
+        mint_signature,
+    ) {
+        (Err(e), Some(mint_signature)) if is_method_not_found(&e) => {
+            let leaf = leaf_proof_from_mint(config, asset_id, mint_signature)?;
+            ensure_asset_owner(asset_id, &leaf.owner, &owner_keypair.pubkey())?;
+            leaf
+        }
+        (result, _) => result?,
+    };
+    if check_root {
+        ensure_root_is_current(&config.client(), &leaf.tree, &leaf.root)?;
+    }
+    let mut instructions = vec![build_transfer_from_proof(
+        &leaf,
+        owner_keypair.pubkey(),
+        new_owner,
+        canopy_depth,
+    )];
+    append_memo(&mut instructions, memo)?;
+    if lookup_tables.is_empty() {
+        ensure_within_legacy_account_limit(
+            config,
+            &instructions,
+            &owner_keypair.pubkey(),
+            &leaf,
+            canopy_depth,
+        )?;
+    }
+
+    submit_with_signer(
+        config,
+        &config.client(),
+        instructions,
+        &owner_keypair,
+        lookup_tables,
+        confirm,
+        auto_compute_units,
+    )
+    .map(|result| result.with_build_started(started))
+}
+
+/// Transfers a batch of compressed NFTs by asset id, e.g. to settle many sales at once.
+///
+/// Each transfer is `(asset_id, new_leaf_owner, leaf_owner_secret_key)` and goes out as
+/// its own transaction paid by that owner. Assets and proofs are fetched in batches
+/// up front, and one RPC client and recent blockhash are shared by every transaction.
+/// Each transfer yields its own result, so a failing item does not abort the rest.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(rpc_url = %config.rpc_url), err)
+)]
+pub fn batch_transfer_by_asset_id(
+    config: &RpcConfig,
+    transfers: &[(String, String, String)],
+    canopy_depth: u32,
+    confirm: bool,
+    auto_compute_units: bool,
+) -> Result<Vec<Result<SubmitResult, NifError>>, NifError> {
+    if transfers.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let asset_ids: Vec<String> = transfers
+        .iter()
+        .map(|(asset_id, _, _)| asset_id.clone())
+        .collect();
+    let leaves = fetch_leaf_proof_batch(&config.rpc_url, &asset_ids)?;
+
+    // Fetch one recent blockhash for the whole batch
+    let client = config.client();
+    let recent_blockhash = get_recent_blockhash_with_client(&client)?;
+
+    let results = transfers
+        .iter()
+        .zip(leaves)
+        .map(
+            |((asset_id, new_leaf_owner, leaf_owner_secret_key), leaf)| {
+                let started = Instant::now();
+                let leaf = leaf?;
+                let new_owner = parse_pubkey(new_leaf_owner)?;
+                let owner_keypair = parse_keypair(leaf_owner_secret_key)?;
+                ensure_asset_owner(asset_id, &leaf.owner, &owner_keypair.pubkey())?;
+
+                let mut instructions = vec![build_transfer_from_proof(
+                    &leaf,
+                    leaf.owner,
+                    new_owner,
+                    canopy_depth,
+                )];
+                if auto_compute_units {
+                    prepend_compute_unit_limit(&client, &mut instructions, &owner_keypair.pubkey());
+                }
+
+                submit_with_resubmits(
+                    config,
+                    &client,
+                    &instructions,
+                    recent_blockhash,
+                    |priced, recent_blockhash| {
+                        let message = Message::new(priced, Some(&owner_keypair.pubkey()));
+                        let mut tx = Transaction::new_unsigned(message);
+                        tx.try_sign(&[&owner_keypair], recent_blockhash)
+                            .map_err(|e| NifError::SerializationError(e.to_string()))?;
+                        Ok(tx)
+                    },
+                    |tx| {
+                        submit_tx_with_client(
+                            &client,
+                            tx,
+                            confirm,
+                            config.skip_preflight,
+                            config.rpc_max_retries,
+                            config.confirm_timeout,
+                        )
+                    },
+                )
+                .map(|result| result.with_build_started(started))
+            },
+        )
+        .collect();
+
+    Ok(results)
+}
+
+/// Builds a `Transfer` instruction for a leaf from its DAS proof
+pub fn build_transfer_from_proof(
+    leaf: &LeafProof,
+    leaf_owner: Pubkey,
+    new_leaf_owner: Pubkey,
+    canopy_depth: u32,
+) -> Instruction {
+    let (tree_config, _) = TreeConfig::find_pda(&leaf.tree);
+
+    // The owner signs and pays, so the delegate only needs to be passed along
+    TransferBuilder::new()
+        .tree_config(tree_config)
+        .merkle_tree(leaf.tree)
+        .leaf_owner(leaf_owner, true)
+        .leaf_delegate(leaf.delegate, false)
+        .new_leaf_owner(new_leaf_owner)
+        .root(leaf.root)
+        .data_hash(leaf.data_hash)
+        .creator_hash(leaf.creator_hash)
+        .nonce(leaf.nonce)
+        .index(leaf.index)
+        .add_remaining_accounts(&leaf.proof_accounts(canopy_depth))
+        .instruction()
+}
+
+/// Burns a compressed NFT knowing only its asset id, fetching the proof via DAS.
+///
+/// `lookup_tables` and `check_root` work as in [`transfer_by_asset_id`].
+#[allow(clippy::too_many_arguments)]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(rpc_url = %config.rpc_url), err)
+)]
+pub fn burn_by_asset_id(
+    config: &RpcConfig,
+    asset_id: &str,
+    leaf_owner_secret_key: &str,
+    canopy_depth: u32,
+    lookup_tables: &[String],
+    confirm: bool,
+    auto_compute_units: bool,
+    check_root: bool,
+) -> Result<SubmitResult, NifError> {
+    let started = Instant::now();
+    let owner_keypair = parse_keypair(leaf_owner_secret_key)?;
+    let leaf = fetch_owned_leaf_proof(&config.rpc_url, asset_id, &owner_keypair.pubkey())?;
+    if check_root {
+        ensure_root_is_current(&config.client(), &leaf.tree, &leaf.root)?;
+    }
+    let instruction = build_burn_from_proof(&leaf, canopy_depth);
+    if lookup_tables.is_empty() {
+        ensure_within_legacy_account_limit(
+            config,
+            std::slice::from_ref(&instruction),
+            &owner_keypair.pubkey(),
+            &leaf,
+            canopy_depth,
+        )?;
+    }
+
+    submit_with_signer(
+        config,
+        &config.client(),
+        vec![instruction],
+        &owner_keypair,
+        lookup_tables,
+        confirm,
+        auto_compute_units,
+    )
+    .map(|result| result.with_build_started(started))
+}
+
+/// Fails before anything is sent when a legacy transaction of `instructions` would
+/// reference more than `config.max_legacy_accounts` accounts, which happens when a deep
+/// tree's proof isn't trimmed enough by its canopy.
+fn ensure_within_legacy_account_limit(
+    config: &RpcConfig,
+    instructions: &[Instruction],
+    payer: &Pubkey,
+    leaf: &LeafProof,
+    canopy_depth: u32,
+) -> Result<(), NifError> {
+    let accounts = Message::new(instructions, Some(payer)).account_keys.len();
+    if accounts <= config.max_legacy_accounts {
+        return Ok(());
+    }
+    Err(NifError::InvalidMetadata(format!(
+        "transaction needs {} accounts ({} proof nodes after trimming a canopy of {}), \
+         more than the {} a legacy transaction fits; pass lookup_tables to send it as a \
+         versioned transaction",
+        accounts,
+        leaf.proof_accounts(canopy_depth).len(),
+        canopy_depth,
+        config.max_legacy_accounts
+    )))
+}
+
+/// Builds a `Burn` instruction for a leaf from its DAS proof, signed by its owner
+pub fn build_burn_from_proof(leaf: &LeafProof, canopy_depth: u32) -> Instruction {
+    let (tree_config, _) = TreeConfig::find_pda(&leaf.tree);
+
+    BurnBuilder::new()
+        .tree_config(tree_config)
+        .leaf_owner(leaf.owner, true)
+        .leaf_delegate(leaf.delegate, false)
+        .merkle_tree(leaf.tree)
+        .root(leaf.root)
+        .data_hash(leaf.data_hash)
+        .creator_hash(leaf.creator_hash)
+        .nonce(leaf.nonce)
+        .index(leaf.index)
+        .add_remaining_accounts(&leaf.proof_accounts(canopy_depth))
+        .instruction()
+}
+
+/// Revokes the delegate of a compressed NFT by asset id, handing authority back to
+/// its owner. The proof is fetched via DAS and `lookup_tables` works as in
+/// [`transfer_by_asset_id`].
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(rpc_url = %config.rpc_url), err)
+)]
+pub fn revoke_delegate(
+    config: &RpcConfig,
+    asset_id: &str,
+    leaf_owner_secret_key: &str,
+    canopy_depth: u32,
+    lookup_tables: &[String],
+    confirm: bool,
+    auto_compute_units: bool,
+) -> Result<SubmitResult, NifError> {
+    let started = Instant::now();
+    let owner_keypair = parse_keypair(leaf_owner_secret_key)?;
+    let leaf = fetch_owned_leaf_proof(&config.rpc_url, asset_id, &owner_keypair.pubkey())?;
+    let instruction = build_revoke_delegate_from_proof(&leaf, canopy_depth);
+
+    submit_with_signer(
+        config,
+        &config.client(),
+        vec![instruction],
+        &owner_keypair,
+        lookup_tables,
+        confirm,
+        auto_compute_units,
+    )
+    .map(|result| result.with_build_started(started))
+}
+
+/// Builds a `Delegate` instruction that sets a leaf's delegate back to its owner
+pub fn build_revoke_delegate_from_proof(leaf: &LeafProof, canopy_depth: u32) -> Instruction {
+    build_delegate_from_proof(leaf, leaf.owner, canopy_depth)
+}
+
+/// Sets a delegate on a compressed NFT and transfers it in one transaction, signed by
+/// its owner, so there is no window where the delegate is set but the transfer has
+/// not happened.
+///
+/// The proof is fetched once via DAS. The `Delegate` instruction comes first and
+/// rewrites the leaf, so the `Transfer` after it is built against the leaf with the
+/// new delegate and the root that leaf produces. `lookup_tables` works as in
+/// [`transfer_by_asset_id`].
+#[allow(clippy::too_many_arguments)]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(rpc_url = %config.rpc_url), err)
+)]
+pub fn approve_and_transfer(
+    config: &RpcConfig,
+    asset_id: &str,
+    new_leaf_delegate: &str,
+    new_leaf_owner: &str,
+    leaf_owner_secret_key: &str,
+    canopy_depth: u32,
+    lookup_tables: &[String],
+    confirm: bool,
+    auto_compute_units: bool,
+) -> Result<SubmitResult, NifError> {
+    let started = Instant::now();
+    let new_delegate = parse_pubkey(new_leaf_delegate)?;
+    let new_owner = parse_pubkey(new_leaf_owner)?;
+    let owner_keypair = parse_keypair(leaf_owner_secret_key)?;
+    let leaf = fetch_owned_leaf_proof(&config.rpc_url, asset_id, &owner_keypair.pubkey())?;
+    let instructions =
+        build_approve_and_transfer_from_proof(&leaf, new_delegate, new_owner, canopy_depth);
+
+    submit_with_signer(
+        config,
+        &config.client(),
+        instructions,
+        &owner_keypair,
+        lookup_tables,
+        confirm,
+        auto_compute_units,
+    )
+    .map(|result| result.with_build_started(started))
+}
+
+/// Builds the `Delegate` then `Transfer` instructions for [`approve_and_transfer`]
+pub fn build_approve_and_transfer_from_proof(
+    leaf: &LeafProof,
+    new_leaf_delegate: Pubkey,
+    new_leaf_owner: Pubkey,
+    canopy_depth: u32,
+) -> Vec<Instruction> {
+    let delegated = leaf.with_delegate(new_leaf_delegate);
+    vec![
+        build_delegate_from_proof(leaf, new_leaf_delegate, canopy_depth),
+        build_transfer_from_proof(&delegated, leaf.owner, new_leaf_owner, canopy_depth),
+    ]
+}
+
+/// Builds a `Delegate` instruction that sets a leaf's delegate to `new_leaf_delegate`
+pub fn build_delegate_from_proof(
+    leaf: &LeafProof,
+    new_leaf_delegate: Pubkey,
+    canopy_depth: u32,
+) -> Instruction {
+    let (tree_config, _) = TreeConfig::find_pda(&leaf.tree);
+
+    DelegateBuilder::new()
+        .tree_config(tree_config)
+        .leaf_owner(leaf.owner)
+        .previous_leaf_delegate(leaf.delegate)
+        .new_leaf_delegate(new_leaf_delegate)
+        .merkle_tree(leaf.tree)
+        .root(leaf.root)
+        .data_hash(leaf.data_hash)
+        .creator_hash(leaf.creator_hash)
+        .nonce(leaf.nonce)
+        .index(leaf.index)
+        .add_remaining_accounts(&leaf.proof_accounts(canopy_depth))
+        .instruction()
+}
+
+/// Enables or disables decompression of the leaves in a tree.
+///
+/// Only the tree creator recorded in the `TreeConfig` may change this, so the keypair
+/// is checked against it before anything is submitted.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(rpc_url = %config.rpc_url), err)
+)]
+pub fn set_decompressible_state(
+    config: &RpcConfig,
+    tree_config_pubkey: &str,
+    tree_creator_secret_key: &str,
+    enabled: bool,
+    confirm: bool,
+    auto_compute_units: bool,
+) -> Result<SubmitResult, NifError> {
+    let started = Instant::now();
+    let tree_config = parse_pubkey(tree_config_pubkey)?;
+    let tree_creator_keypair = parse_keypair(tree_creator_secret_key)?;
+
+    let client = config.client();
+    let tree_creator = fetch_tree_config(&client, &tree_config)?.tree_creator;
+    if tree_creator != tree_creator_keypair.pubkey() {
+        return Err(NifError::KeypairMismatch(format!(
+            "tree {} was created by {}, not {}",
+            tree_config,
+            tree_creator,
+            tree_creator_keypair.pubkey()
+        )));
+    }
+
+    let state = if enabled {
+        DecompressibleState::Enabled
+    } else {
+        DecompressibleState::Disabled
+    };
+    let instruction = SetDecompressibleStateBuilder::new()
+        .tree_config(tree_config)
+        .tree_creator(tree_creator)
+        .decompressable_state(state)
+        .instruction();
+
+    submit_with_signer(
+        config,
+        &client,
+        vec![instruction],
+        &tree_creator_keypair,
+        &[],
+        confirm,
+        auto_compute_units,
+    )
+    .map(|result| result.with_build_started(started))
+}
+
+/// Token Metadata program, which owns the collection's metadata and edition accounts
+const TOKEN_METADATA_PROGRAM_ID: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+/// Sets a compressed NFT's collection and verifies it in a single instruction.
+///
+/// `metadata_borsh` is the leaf's current metadata as base64 Borsh, which the program
+/// needs to rehash the leaf; it is checked against the DAS data hash before submitting.
+/// The collection authority signs and pays, and must also be the tree's creator or
+/// delegate. It is checked against the collection's update authority and delegate
+/// records before submitting, rather than failing on-chain as an invalid collection
+/// authority.
+#[allow(clippy::too_many_arguments)]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(rpc_url = %config.rpc_url), err)
+)]
+pub fn set_and_verify_collection(
+    config: &RpcConfig,
+    asset_id: &str,
+    metadata_borsh: &str,
+    collection_mint: &str,
+    collection_metadata: &str,
+    collection_edition: &str,
+    collection_authority_secret_key: &str,
+    canopy_depth: u32,
+    confirm: bool,
+    auto_compute_units: bool,
+) -> Result<SubmitResult, NifError> {
+    let started = Instant::now();
+    let collection_mint = parse_pubkey(collection_mint)?;
+    let (collection_metadata, collection_edition) =
+        validate_collection_accounts(&collection_mint, collection_metadata, collection_edition)?;
+    let authority_keypair = parse_keypair(collection_authority_secret_key)?;
+    let metadata_bytes = decode_metadata_base64(metadata_borsh)?;
+    let metadata = MetadataArgs::try_from_slice(&metadata_bytes)
+        .map_err(|e| NifError::InvalidMetadata(format!("Borsh deserialize error: {}", e)))?;
+
+    let leaf = fetch_leaf_proof(&config.rpc_url, asset_id)?;
+    if compute_data_hash(&metadata)? != leaf.data_hash {
+        return Err(NifError::InvalidMetadata(format!(
+            "metadata does not match the data hash of asset {}",
+            asset_id.trim()
+        )));
+    }
+
+    let (tree_config, _) = TreeConfig::find_pda(&leaf.tree);
+    let client = config.client();
+    let tree_config_account = fetch_tree_config(&client, &tree_config)?;
+    let authority = authority_keypair.pubkey();
+    if authority != tree_config_account.tree_creator
+        && authority != tree_config_account.tree_delegate
+    {
+        return Err(NifError::KeypairMismatch(format!(
+            "{} is neither the creator nor the delegate of tree {}",
+            authority, leaf.tree
+        )));
+    }
+    let collection_authority_record = ensure_collection_authority(
+        &client,
+        &collection_mint,
+        &collection_metadata,
+        &authority,
+        true,
+    )?;
+
+    let instruction = SetAndVerifyCollectionBuilder::new()
+        .tree_config(tree_config)
+        .leaf_owner(leaf.owner)
+        .leaf_delegate(leaf.delegate)
+        .merkle_tree(leaf.tree)
+        .payer(authority)
+        .tree_creator_or_delegate(authority, true)
+        .collection_authority(authority)
+        .collection_authority_record_pda(collection_authority_record)
+        .collection_mint(collection_mint)
+        .collection_metadata(collection_metadata)
+        .collection_edition(collection_edition)
+        .bubblegum_signer(derive_bubblegum_signer())
+        .token_metadata_program(TOKEN_METADATA_PROGRAM_ID)
+        .root(leaf.root)
+        .data_hash(leaf.data_hash)
+        .creator_hash(leaf.creator_hash)
+        .nonce(leaf.nonce)
+        .index(leaf.index)
+        .metadata(metadata)
+        .collection(collection_mint)
+        .add_remaining_accounts(&leaf.proof_accounts(canopy_depth))
+        .instruction();
+
+    submit_with_signer(
+        config,
+        &client,
+        vec![instruction],
+        &authority_keypair,
+        &[],
+        confirm,
+        auto_compute_units,
+    )
+    .map(|result| result.with_build_started(started))
+}
+
+/// Parses the collection's metadata and edition accounts and checks they are the
+/// Token Metadata PDAs of `collection_mint`
+fn validate_collection_accounts(
+    collection_mint: &Pubkey,
+    collection_metadata: &str,
+    collection_edition: &str,
+) -> Result<(Pubkey, Pubkey), NifError> {
+    let metadata = parse_pubkey(collection_metadata)?;
+    let edition = parse_pubkey(collection_edition)?;
+
+    let seeds: &[&[u8]] = &[
+        b"metadata",
+        TOKEN_METADATA_PROGRAM_ID.as_ref(),
+        collection_mint.as_ref(),
+    ];
+    let (expected_metadata, _) = Pubkey::find_program_address(seeds, &TOKEN_METADATA_PROGRAM_ID);
+    if metadata != expected_metadata {
+        return Err(NifError::InvalidPubkey(format!(
+            "{} is not the metadata account of collection {}",
+            metadata, collection_mint
+        )));
+    }
+
+    let (expected_edition, _) =
+        Pubkey::find_program_address(&[seeds, &[b"edition"]].concat(), &TOKEN_METADATA_PROGRAM_ID);
+    if edition != expected_edition {
+        return Err(NifError::InvalidPubkey(format!(
+            "{} is not the master edition of collection {}",
+            edition, collection_mint
+        )));
+    }
+
+    Ok((metadata, edition))
+}
+
+/// `Key::MetadataV1`, the first byte of a Token Metadata metadata account
+const METADATA_V1_KEY: u8 = 4;
+
+/// Token Metadata's `SetCollectionSize` instruction tag
+const SET_COLLECTION_SIZE_DISCRIMINATOR: u8 = 34;
+
+/// Sets the number of items a collection tracks, signed by its collection authority.
+///
+/// Bubblegum has no instruction of its own for this: it updates the size itself when
+/// verifying items, so this sends Token Metadata's `SetCollectionSize`, which gives a
+/// collection that doesn't track a size yet its starting size. The authority is
+/// checked first: Token Metadata takes the update authority or a legacy collection
+/// authority record here, not a collection delegate.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(rpc_url = %config.rpc_url), err)
+)]
+pub fn set_collection_size(
+    config: &RpcConfig,
+    collection_mint: &str,
+    collection_authority_secret_key: &str,
+    size: u64,
+    confirm: bool,
+    auto_compute_units: bool,
+) -> Result<SubmitResult, NifError> {
+    let started = Instant::now();
+    let collection_mint = parse_pubkey(collection_mint)?;
+    let authority_keypair = parse_keypair(collection_authority_secret_key)?;
+    let authority = authority_keypair.pubkey();
+    let (collection_metadata, _) = Pubkey::find_program_address(
+        &[
+            b"metadata",
+            TOKEN_METADATA_PROGRAM_ID.as_ref(),
+            collection_mint.as_ref(),
+        ],
+        &TOKEN_METADATA_PROGRAM_ID,
+    );
+
+    let client = config.client();
+    let collection_authority_record = ensure_collection_authority(
+        &client,
+        &collection_mint,
+        &collection_metadata,
+        &authority,
+        false,
+    )?;
+    let instruction = build_set_collection_size_instruction(
+        &collection_metadata,
+        &authority,
+        &collection_mint,
+        collection_authority_record,
+        size,
+    );
+
+    submit_with_signer(
+        config,
+        &client,
+        vec![instruction],
+        &authority_keypair,
+        &[],
+        confirm,
+        auto_compute_units,
+    )
+    .map(|result| result.with_build_started(started))
+}
+
+/// Builds Token Metadata's `SetCollectionSize`, passing the collection authority
+/// record when a delegate signs
+pub fn build_set_collection_size_instruction(
+    collection_metadata: &Pubkey,
+    collection_authority: &Pubkey,
+    collection_mint: &Pubkey,
+    collection_authority_record: Option<Pubkey>,
+    size: u64,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*collection_metadata, false),
+        AccountMeta::new(*collection_authority, true),
+        AccountMeta::new_readonly(*collection_mint, false),
+    ];
+    if let Some(record) = collection_authority_record {
+        accounts.push(AccountMeta::new_readonly(record, false));
+    }
+
+    let mut data = vec![SET_COLLECTION_SIZE_DISCRIMINATOR];
+    data.extend_from_slice(&size.to_le_bytes());
+    Instruction::new_with_bytes(TOKEN_METADATA_PROGRAM_ID, &data, accounts)
+}
+
+/// Checks that `authority` controls the collection whose metadata account is
+/// `collection_metadata`: it is either the update authority, or holds a collection
+/// authority record from it. A collection delegate record counts too when
+/// `allow_collection_delegate` is set; Bubblegum accepts one, Token Metadata's
+/// `SetCollectionSize` doesn't.
+///
+/// Returns the record to pass along when `authority` is a delegate.
+fn ensure_collection_authority(
+    client: &dyn RpcBackend,
+    collection_mint: &Pubkey,
+    collection_metadata: &Pubkey,
+    authority: &Pubkey,
+    allow_collection_delegate: bool,
+) -> Result<Option<Pubkey>, NifError> {
+    let account = client
+        .get_account(collection_metadata)
+        .map_err(classify_client_error)?;
+    if account.owner != TOKEN_METADATA_PROGRAM_ID {
+        return Err(NifError::InvalidMetadata(format!(
+            "{} is not a Token Metadata account",
+            collection_metadata
+        )));
+    }
+    let update_authority = collection_update_authority(&account.data, collection_mint)?;
+    if *authority == update_authority {
+        return Ok(None);
+    }
+
+    let seeds: &[&[u8]] = &[
+        b"metadata",
+        TOKEN_METADATA_PROGRAM_ID.as_ref(),
+        collection_mint.as_ref(),
+    ];
+    let mut records = vec![[seeds, &[b"collection_authority", authority.as_ref()]].concat()];
+    if allow_collection_delegate {
+        records.push(
+            [
+                seeds,
+                &[
+                    b"collection_delegate",
+                    update_authority.as_ref(),
+                    authority.as_ref(),
+                ],
+            ]
+            .concat(),
+        );
+    }
+    for record_seeds in &records {
+        let (record, _) = Pubkey::find_program_address(record_seeds, &TOKEN_METADATA_PROGRAM_ID);
+        // A record is rent-exempt, so a zero balance means it was never created
+        if client.get_balance(&record).map_err(classify_client_error)? > 0 {
+            return Ok(Some(record));
+        }
+    }
+
+    Err(NifError::InvalidMetadata(format!(
+        "{} is neither the update authority ({}) nor a delegate of collection {}",
+        authority, update_authority, collection_mint
+    )))
+}
+
+/// Reads the update authority out of a collection's metadata account data, checking
+/// it belongs to `collection_mint`
+fn collection_update_authority(data: &[u8], collection_mint: &Pubkey) -> Result<Pubkey, NifError> {
+    if data.len() < 65 || data[0] != METADATA_V1_KEY {
+        return Err(NifError::InvalidMetadata(
+            "collection metadata account is not a metadata account".to_string(),
+        ));
+    }
+    let mint = Pubkey::new_from_array(data[33..65].try_into().unwrap());
+    if mint != *collection_mint {
+        return Err(NifError::InvalidMetadata(format!(
+            "metadata account belongs to mint {}, not collection {}",
+            mint, collection_mint
+        )));
+    }
+    Ok(Pubkey::new_from_array(data[1..33].try_into().unwrap()))
+}
+
+/// Transfers a compressed NFT by asset id in a durable-nonce transaction.
+///
+/// See [`mint_v1_with_nonce`] for how the nonce is used.
+#[allow(clippy::too_many_arguments)]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(rpc_url = %config.rpc_url), err)
+)]
+pub fn transfer_by_asset_id_with_nonce(
+    config: &RpcConfig,
+    asset_id: &str,
+    new_leaf_owner: &str,
+    leaf_owner_secret_key: &str,
+    nonce_account: &str,
+    nonce_authority_secret_key: &str,
+    canopy_depth: u32,
+    confirm: bool,
+    memo: Option<&str>,
+) -> Result<SubmitResult, NifError> {
+    let started = Instant::now();
+    let new_owner = parse_pubkey(new_leaf_owner)?;
+    let owner_keypair = parse_keypair(leaf_owner_secret_key)?;
+    let nonce_account = parse_pubkey(nonce_account)?;
+    let nonce_authority = parse_keypair(nonce_authority_secret_key)?;
+
+    let leaf = fetch_owned_leaf_proof(&config.rpc_url, asset_id, &owner_keypair.pubkey())?;
+    let mut instructions = vec![build_transfer_from_proof(
+        &leaf,
+        owner_keypair.pubkey(),
+        new_owner,
+        canopy_depth,
+    )];
+    append_memo(&mut instructions, memo)?;
+
+    let client = config.client();
+    submit_with_nonce(
+        &client,
+        config,
+        instructions,
+        &owner_keypair,
+        &nonce_account,
+        &nonce_authority,
+        confirm,
+    )
+    .map(|result| result.with_build_started(started))
+}
+
+/// Signs `instructions` against the blockhash stored in `nonce_account`, with the
+/// `advance_nonce_account` instruction prepended, then submits them.
+fn submit_with_nonce(
+    client: &dyn RpcBackend,
+    config: &RpcConfig,
+    instructions: Vec<Instruction>,
+    payer_keypair: &Keypair,
+    nonce_account: &Pubkey,
+    nonce_authority: &Keypair,
+    confirm: bool,
+) -> Result<SubmitResult, NifError> {
+    let tx = sign_with_nonce(
+        client,
+        config,
+        instructions,
+        payer_keypair,
+        nonce_account,
+        nonce_authority,
+    )?;
+    submit_tx_with_client(
+        client,
+        tx,
+        confirm,
+        config.skip_preflight,
+        config.rpc_max_retries,
+        config.confirm_timeout,
+    )
+}
+
+/// The signing half of [`submit_with_nonce`]
+fn sign_with_nonce(
+    client: &dyn RpcBackend,
+    config: &RpcConfig,
+    mut instructions: Vec<Instruction>,
+    payer_keypair: &Keypair,
+    nonce_account: &Pubkey,
+    nonce_authority: &Keypair,
+) -> Result<Transaction, NifError> {
+    prepend_compute_unit_price(&mut instructions, config.priority_fee);
+    prepend_advance_nonce(&mut instructions, nonce_account, &nonce_authority.pubkey());
+    let nonce_blockhash = fetch_nonce_blockhash(client, nonce_account)?;
+
+    let message = Message::new(&instructions, Some(&payer_keypair.pubkey()));
     let mut tx = Transaction::new_unsigned(message);
-    tx.try_sign(&[&payer_keypair, &leaf_owner_keypair], recent_blockhash)
+    let mut signers = vec![payer_keypair];
+    if nonce_authority.pubkey() != payer_keypair.pubkey() {
+        signers.push(nonce_authority);
+    }
+    tx.try_sign(&signers, nonce_blockhash)
         .map_err(|e| NifError::SerializationError(e.to_string()))?;
+    Ok(tx)
+}
 
-    submit_tx(rpc_url, tx)
+/// Signs instructions with a single keypair acting as signer and fee payer, then submits them.
+///
+/// A legacy transaction is used unless `lookup_tables` are given, in which case the
+/// tables are fetched and the instructions are compiled into a v0 transaction.
+///
+/// Expired, unconfirmed transactions are resubmitted as in [`submit_with_resubmits`].
+fn submit_with_signer(
+    config: &RpcConfig,
+    client: &dyn RpcBackend,
+    mut instructions: Vec<Instruction>,
+    signer_keypair: &Keypair,
+    lookup_tables: &[String],
+    confirm: bool,
+    auto_compute_units: bool,
+) -> Result<SubmitResult, NifError> {
+    if auto_compute_units {
+        prepend_compute_unit_limit(client, &mut instructions, &signer_keypair.pubkey());
+    }
+    let lookup_tables = if lookup_tables.is_empty() {
+        Vec::new()
+    } else {
+        fetch_lookup_tables(client, lookup_tables)?
+    };
+
+    let recent_blockhash = get_recent_blockhash_with_client(client)?;
+    submit_with_resubmits(
+        config,
+        client,
+        &instructions,
+        recent_blockhash,
+        |priced, recent_blockhash| {
+            if lookup_tables.is_empty() {
+                let message = Message::new(priced, Some(&signer_keypair.pubkey()));
+                let mut tx = Transaction::new_unsigned(message);
+                tx.try_sign(&[signer_keypair], recent_blockhash)
+                    .map_err(|e| NifError::SerializationError(e.to_string()))?;
+                Ok(VersionedTransaction::from(tx))
+            } else {
+                build_versioned_transaction(
+                    priced,
+                    &signer_keypair.pubkey(),
+                    &[signer_keypair],
+                    &lookup_tables,
+                    recent_blockhash,
+                )
+            }
+        },
+        |tx| {
+            submit_versioned_tx_with_client(
+                client,
+                tx,
+                confirm,
+                config.skip_preflight,
+                config.rpc_max_retries,
+                config.confirm_timeout,
+            )
+        },
+    )
 }
 
 // ---------------Tests------------------------
@@ -160,8 +1649,10 @@ pub fn transfer(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::backend::MockRpc;
     use mpl_bubblegum::types::{Creator, TokenProgramVersion};
     use solana_client::rpc_client::RpcClient;
+    use solana_sdk::account::Account;
     use std::thread::sleep;
     use std::time::Duration;
 
@@ -244,17 +1735,25 @@ mod tests {
         let tree_creator_secret_key = tree_creator.to_base58_string();
 
         let result = create_tree_config(
-            RPC_URL,
+            &RpcConfig::new(RPC_URL),
             &payer_pubkey,
             &tree_creator_pubkey,
+            &Pubkey::new_unique().to_string(),
             14,   // max_depth (example value)
             2048, // max_buffer_size (example value)
             &payer_secret_key,
             &tree_creator_secret_key,
+            true,
+            false,
+            false,
+            &[],
         );
 
         match result {
-            Ok(signature) => assert!(!signature.is_empty(), "Signature should not be empty"),
+            Ok(result) => assert!(
+                !result.signature.is_empty(),
+                "Signature should not be empty"
+            ),
             Err(NifError::RpcError(msg)) => {
                 // Tolerate account not found since payer isn’t funded
                 assert!(
@@ -267,19 +1766,69 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_build_create_tree_config_instruction() {
+        let payer = Pubkey::new_unique();
+        let tree_creator = Pubkey::new_unique();
+        let merkle_tree = Pubkey::new_unique();
+
+        let instruction =
+            build_create_tree_config_instruction(&payer, &tree_creator, &merkle_tree, 14, 64);
+
+        let (tree_config, _) =
+            Pubkey::find_program_address(&[merkle_tree.as_ref()], &mpl_bubblegum::ID);
+        assert_eq!(instruction.program_id, mpl_bubblegum::ID);
+        assert_eq!(instruction.accounts[0].pubkey, tree_config);
+        assert_eq!(instruction.accounts[1].pubkey, merkle_tree);
+        assert_eq!(instruction.accounts[2].pubkey, payer);
+        assert_eq!(instruction.accounts[3].pubkey, tree_creator);
+    }
+
+    #[test]
+    fn test_select_signers() {
+        let payer = Keypair::new();
+        let co_signer = Keypair::new();
+        let unused = Keypair::new();
+        let instruction = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[],
+            vec![solana_sdk::instruction::AccountMeta::new_readonly(
+                co_signer.pubkey(),
+                true,
+            )],
+        );
+        let message = Message::new(&[instruction], Some(&payer.pubkey()));
+
+        let signers = select_signers(&message, &[&unused, &co_signer, &payer]).unwrap();
+        let signers: Vec<Pubkey> = signers.iter().map(|signer| signer.pubkey()).collect();
+        assert_eq!(signers, vec![payer.pubkey(), co_signer.pubkey()]);
+
+        let error = match select_signers(&message, &[&unused]) {
+            Err(NifError::InvalidKeypair(error)) => error,
+            _ => panic!("expected InvalidKeypair"),
+        };
+        assert!(error.contains(&payer.pubkey().to_string()));
+        assert!(error.contains(&co_signer.pubkey().to_string()));
+    }
+
     #[test]
     fn test_create_tree_config_invalid_payer_pubkey() {
         let tree_creator = Keypair::new();
         let payer = Keypair::new();
 
         let result = create_tree_config(
-            RPC_URL,
+            &RpcConfig::new(RPC_URL),
             "invalid_payer_pubkey",
             &tree_creator.pubkey().to_string(),
+            &Pubkey::new_unique().to_string(),
             14,
             2048,
             &payer.to_base58_string(),
             &tree_creator.to_base58_string(),
+            true,
+            false,
+            false,
+            &[],
         );
 
         assert!(result.is_err(), "Should fail with invalid payer pubkey");
@@ -296,13 +1845,18 @@ mod tests {
         let tree_creator = Keypair::new();
 
         let result = create_tree_config(
-            RPC_URL,
+            &RpcConfig::new(RPC_URL),
             &payer.pubkey().to_string(),
             &tree_creator.pubkey().to_string(),
+            &Pubkey::new_unique().to_string(),
             14,
             2048,
             "invalid_secret_key",
             &tree_creator.to_base58_string(),
+            true,
+            false,
+            false,
+            &[],
         );
 
         assert!(result.is_err(), "Should fail with invalid secret key");
@@ -331,17 +1885,26 @@ mod tests {
             .expect("Failed to serialize metadata for test");
 
         let result = mint_v1(
-            RPC_URL,
+            &RpcConfig::new(RPC_URL),
             &tree.pubkey().to_string(),
             &leaf_owner.pubkey().to_string(),
             &leaf_delegate.pubkey().to_string(),
             &metadata_borsh,
             &payer.to_base58_string(),
             &leaf_owner.to_base58_string(),
+            true,
+            false,
+            None,
+            None,
+            false,
+            false,
         );
 
         match result {
-            Ok(signature) => assert!(!signature.is_empty(), "Signature should not be empty"),
+            Ok(result) => assert!(
+                !result.signature.is_empty(),
+                "Signature should not be empty"
+            ),
             Err(NifError::RpcError(msg)) => {
                 // Tolerate account not found since accounts aren’t funded
                 assert!(
@@ -365,13 +1928,19 @@ mod tests {
             .expect("Failed to serialize metadata for test");
 
         let result = mint_v1(
-            RPC_URL,
+            &RpcConfig::new(RPC_URL),
             "invalid_tree_pubkey",
             &leaf_owner.pubkey().to_string(),
             &leaf_delegate.pubkey().to_string(),
             &metadata_borsh,
             &payer.to_base58_string(),
             &leaf_owner.to_base58_string(),
+            true,
+            false,
+            None,
+            None,
+            false,
+            false,
         );
 
         assert!(result.is_err(), "Should fail with invalid tree pubkey");
@@ -390,13 +1959,19 @@ mod tests {
         let leaf_delegate = Keypair::new();
 
         let result = mint_v1(
-            RPC_URL,
+            &RpcConfig::new(RPC_URL),
             &tree.pubkey().to_string(),
             &leaf_owner.pubkey().to_string(),
             &leaf_delegate.pubkey().to_string(),
             "not_a_valid_borsh_base64_string",
             &payer.to_base58_string(),
             &leaf_owner.to_base58_string(),
+            true,
+            false,
+            None,
+            None,
+            false,
+            false,
         );
 
         assert!(result.is_err(), "Should fail with invalid metadata");
@@ -407,6 +1982,34 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_mint_v1_raw_invalid_fee_payer() {
+        let payer = Keypair::new();
+        let tree = Keypair::new();
+        let leaf_owner = Keypair::new();
+
+        let result = mint_v1_raw(
+            &RpcConfig::new(RPC_URL),
+            &tree.pubkey().to_string(),
+            &leaf_owner.pubkey().to_string(),
+            &leaf_owner.pubkey().to_string(),
+            &[],
+            &payer.to_base58_string(),
+            &leaf_owner.to_base58_string(),
+            true,
+            false,
+            None,
+            Some("invalid_fee_payer"),
+            false,
+            false,
+        );
+
+        assert!(
+            matches!(result, Err(NifError::InvalidKeypair(_))),
+            "Should fail with an invalid fee payer secret key"
+        );
+    }
+
     #[test]
     fn test_transfer_success() {
         let payer = Keypair::new();
@@ -420,17 +2023,25 @@ mod tests {
         //     .expect("Failed to airdrop to leaf owner");
 
         let result = transfer(
-            RPC_URL,
+            &RpcConfig::new(RPC_URL),
             &tree.pubkey().to_string(),
             &leaf_owner.pubkey().to_string(),
             &new_leaf_owner.pubkey().to_string(),
             0, // leaf_index
             &payer.to_base58_string(),
             &leaf_owner.to_base58_string(),
+            true,
+            false,
+            None,
+            false,
+            None,
         );
 
         match result {
-            Ok(signature) => assert!(!signature.is_empty(), "Signature should not be empty"),
+            Ok(result) => assert!(
+                !result.signature.is_empty(),
+                "Signature should not be empty"
+            ),
             Err(NifError::RpcError(msg)) => {
                 // Tolerate account not found since accounts aren’t funded
                 assert!(
@@ -443,6 +2054,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_transfer_dry_run() {
+        let payer = Keypair::new();
+        let tree = Keypair::new();
+        let leaf_owner = Keypair::new();
+        let new_leaf_owner = Keypair::new();
+
+        let result = transfer(
+            &RpcConfig::new(RPC_URL),
+            &tree.pubkey().to_string(),
+            &leaf_owner.pubkey().to_string(),
+            &new_leaf_owner.pubkey().to_string(),
+            0,
+            &payer.to_base58_string(),
+            &leaf_owner.to_base58_string(),
+            true,
+            false,
+            None,
+            true,
+            None,
+        )
+        .expect("Dry run should not fail on unfunded accounts");
+
+        // Nothing is sent, so the failure shows up in the simulation instead
+        let simulation = result.simulation.expect("Dry run should simulate");
+        assert!(simulation.err.is_some(), "Unfunded payer should fail");
+        assert!(result.slot.is_none());
+        assert!(result.explorer_url.is_none());
+    }
+
     #[test]
     fn test_transfer_invalid_leaf_owner() {
         let payer = Keypair::new();
@@ -451,13 +2092,18 @@ mod tests {
         let leaf_owner = Keypair::new();
 
         let result = transfer(
-            RPC_URL,
+            &RpcConfig::new(RPC_URL),
             &tree.pubkey().to_string(),
             "invalid_leaf_owner",
             &new_leaf_owner.pubkey().to_string(),
             0,
             &payer.to_base58_string(),
             &leaf_owner.to_base58_string(),
+            true,
+            false,
+            None,
+            false,
+            None,
         );
 
         assert!(result.is_err(), "Should fail with invalid leaf owner");
@@ -475,13 +2121,18 @@ mod tests {
         let new_leaf_owner = Keypair::new();
 
         let result = transfer(
-            RPC_URL,
+            &RpcConfig::new(RPC_URL),
             &tree.pubkey().to_string(),
             &leaf_owner.pubkey().to_string(),
             &new_leaf_owner.pubkey().to_string(),
             0,
             "invalid_secret_key",
             &leaf_owner.to_base58_string(),
+            true,
+            false,
+            None,
+            false,
+            None,
         );
 
         assert!(result.is_err(), "Should fail with invalid secret key");
@@ -492,6 +2143,57 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_transfer_memo_too_long() {
+        let tree = Keypair::new();
+        let leaf_owner = Keypair::new();
+        let new_leaf_owner = Keypair::new();
+        let memo = "a".repeat(501);
+
+        let result = transfer(
+            &RpcConfig::new(RPC_URL),
+            &tree.pubkey().to_string(),
+            &leaf_owner.pubkey().to_string(),
+            &new_leaf_owner.pubkey().to_string(),
+            0,
+            &leaf_owner.to_base58_string(),
+            &leaf_owner.to_base58_string(),
+            true,
+            false,
+            Some(&memo),
+            false,
+            None,
+        );
+
+        assert!(
+            matches!(result, Err(NifError::InvalidMetadata(_))),
+            "Should reject a memo over 500 bytes"
+        );
+    }
+
+    #[test]
+    fn test_transfer_signer_roles() {
+        let owner = Pubkey::new_unique();
+        let delegate = Pubkey::new_unique();
+
+        assert_eq!(
+            transfer_signer_roles(&owner, &delegate, &owner).unwrap(),
+            (true, false)
+        );
+        assert_eq!(
+            transfer_signer_roles(&owner, &delegate, &delegate).unwrap(),
+            (false, true)
+        );
+        assert_eq!(
+            transfer_signer_roles(&owner, &owner, &owner).unwrap(),
+            (true, false)
+        );
+        assert!(matches!(
+            transfer_signer_roles(&owner, &delegate, &Pubkey::new_unique()),
+            Err(NifError::KeypairMismatch(_))
+        ));
+    }
+
     // Edge case: Test with a large leaf_index
     #[test]
     fn test_transfer_large_leaf_index() {
@@ -501,17 +2203,25 @@ mod tests {
         let new_leaf_owner = Keypair::new();
 
         let result = transfer(
-            RPC_URL,
+            &RpcConfig::new(RPC_URL),
             &tree.pubkey().to_string(),
             &leaf_owner.pubkey().to_string(),
             &new_leaf_owner.pubkey().to_string(),
             u32::MAX, // Max possible leaf_index
             &payer.to_base58_string(),
             &leaf_owner.to_base58_string(),
+            true,
+            false,
+            None,
+            false,
+            None,
         );
 
         match result {
-            Ok(signature) => assert!(!signature.is_empty(), "Signature should not be empty"),
+            Ok(result) => assert!(
+                !result.signature.is_empty(),
+                "Signature should not be empty"
+            ),
             Err(NifError::RpcError(msg)) => {
                 assert!(
                     msg.contains("AccountNotFound") || msg.contains("MinimumBalance"),
@@ -522,4 +2232,526 @@ mod tests {
             Err(e) => panic!("Unexpected error: {:?}", e),
         }
     }
+
+    #[test]
+    fn test_transfer_by_asset_id_invalid_asset_id() {
+        let leaf_owner = Keypair::new();
+        let new_leaf_owner = Keypair::new();
+
+        let result = transfer_by_asset_id(
+            &RpcConfig::new(RPC_URL),
+            "invalid_asset_id",
+            &new_leaf_owner.pubkey().to_string(),
+            &leaf_owner.to_base58_string(),
+            0,
+            &[],
+            true,
+            false,
+            None,
+            false,
+            None,
+        );
+
+        assert!(result.is_err(), "Should fail with invalid asset id");
+        if let Err(NifError::InvalidPubkey(_)) = result {
+            // Success
+        } else {
+            panic!("Wrong error type");
+        }
+    }
+
+    #[test]
+    fn test_batch_transfer_by_asset_id_empty() {
+        let results = batch_transfer_by_asset_id(&RpcConfig::new(RPC_URL), &[], 0, true, false)
+            .expect("An empty batch should succeed");
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn test_burn_by_asset_id_invalid_secret_key() {
+        let asset = Keypair::new();
+
+        let result = burn_by_asset_id(
+            &RpcConfig::new(RPC_URL),
+            &asset.pubkey().to_string(),
+            "invalid_secret_key",
+            0,
+            &[],
+            true,
+            false,
+            false,
+        );
+
+        assert!(result.is_err(), "Should fail with invalid secret key");
+        if let Err(NifError::InvalidKeypair(_)) = result {
+            // Success
+        } else {
+            panic!("Wrong error type");
+        }
+    }
+
+    #[test]
+    fn test_revoke_delegate_invalid_secret_key() {
+        let asset = Keypair::new();
+
+        let result = revoke_delegate(
+            &RpcConfig::new(RPC_URL),
+            &asset.pubkey().to_string(),
+            "invalid_secret_key",
+            0,
+            &[],
+            true,
+            false,
+        );
+
+        assert!(matches!(result, Err(NifError::InvalidKeypair(_))));
+    }
+
+    #[test]
+    fn test_ensure_within_legacy_account_limit() {
+        let owner = Pubkey::new_unique();
+        let leaf = LeafProof {
+            tree: Pubkey::new_unique(),
+            owner,
+            delegate: owner,
+            root: [1; 32],
+            data_hash: [2; 32],
+            creator_hash: [3; 32],
+            nonce: 4,
+            index: 4,
+            proof: (0..24).map(|_| Pubkey::new_unique()).collect(),
+        };
+        let config = RpcConfig::new(RPC_URL);
+
+        // A depth-24 proof with a canopy of 10 leaves 14 proof accounts, which fits
+        let instruction = build_burn_from_proof(&leaf, 10);
+        assert!(
+            ensure_within_legacy_account_limit(&config, &[instruction], &owner, &leaf, 10).is_ok()
+        );
+
+        let instruction = build_burn_from_proof(&leaf, 0);
+        match ensure_within_legacy_account_limit(&config, &[instruction], &owner, &leaf, 0) {
+            Err(NifError::InvalidMetadata(message)) => {
+                assert!(message.contains("24 proof nodes"));
+                assert!(message.contains("lookup_tables"));
+            }
+            other => panic!("expected an account limit error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_build_revoke_delegate_from_proof() {
+        let owner = Pubkey::new_unique();
+        let delegate = Pubkey::new_unique();
+        let leaf = LeafProof {
+            tree: Pubkey::new_unique(),
+            owner,
+            delegate,
+            root: [1; 32],
+            data_hash: [2; 32],
+            creator_hash: [3; 32],
+            nonce: 4,
+            index: 4,
+            proof: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+        };
+
+        let instruction = build_revoke_delegate_from_proof(&leaf, 1);
+        // tree_config, leaf_owner, previous_leaf_delegate, new_leaf_delegate, ...
+        assert_eq!(instruction.accounts[1].pubkey, owner);
+        assert!(instruction.accounts[1].is_signer);
+        assert_eq!(instruction.accounts[2].pubkey, delegate);
+        assert_eq!(instruction.accounts[3].pubkey, owner);
+        assert_eq!(instruction.accounts.last().unwrap().pubkey, leaf.proof[0]);
+    }
+
+    #[test]
+    fn test_build_approve_and_transfer_from_proof() {
+        let owner = Pubkey::new_unique();
+        let leaf = LeafProof {
+            tree: Pubkey::new_unique(),
+            owner,
+            delegate: owner,
+            root: [1; 32],
+            data_hash: [2; 32],
+            creator_hash: [3; 32],
+            nonce: 4,
+            index: 4,
+            proof: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+        };
+        let new_delegate = Pubkey::new_unique();
+        let new_owner = Pubkey::new_unique();
+
+        let instructions = build_approve_and_transfer_from_proof(&leaf, new_delegate, new_owner, 0);
+        assert_eq!(instructions.len(), 2);
+
+        // Delegate first, proven against the current root
+        let delegate = &instructions[0];
+        assert_eq!(delegate.accounts[3].pubkey, new_delegate);
+        assert_eq!(delegate.data[8..40], leaf.root);
+
+        // Then the transfer, proven against the root after the delegate change
+        let transfer = &instructions[1];
+        assert_eq!(transfer.accounts[1].pubkey, owner);
+        assert!(transfer.accounts[1].is_signer);
+        assert_eq!(transfer.accounts[2].pubkey, new_delegate);
+        assert_eq!(transfer.accounts[3].pubkey, new_owner);
+        assert_eq!(transfer.data[8..40], leaf.with_delegate(new_delegate).root);
+    }
+
+    #[test]
+    fn test_approve_and_transfer_invalid_delegate() {
+        let owner = Keypair::new();
+
+        let result = approve_and_transfer(
+            &RpcConfig::new(RPC_URL),
+            &Pubkey::new_unique().to_string(),
+            "invalid_delegate",
+            &Pubkey::new_unique().to_string(),
+            &owner.to_base58_string(),
+            0,
+            &[],
+            true,
+            false,
+        );
+
+        assert!(matches!(result, Err(NifError::InvalidPubkey(_))));
+    }
+
+    #[test]
+    fn test_set_decompressible_state_invalid_tree_config() {
+        let tree_creator = Keypair::new();
+
+        let result = set_decompressible_state(
+            &RpcConfig::new(RPC_URL),
+            "invalid_tree_config",
+            &tree_creator.to_base58_string(),
+            true,
+            true,
+            false,
+        );
+
+        assert!(result.is_err(), "Should fail with invalid tree config");
+        if let Err(NifError::InvalidPubkey(_)) = result {
+            // Success
+        } else {
+            panic!("Wrong error type");
+        }
+    }
+
+    fn collection_pdas(mint: &Pubkey) -> (Pubkey, Pubkey) {
+        let seeds: &[&[u8]] = &[
+            b"metadata",
+            TOKEN_METADATA_PROGRAM_ID.as_ref(),
+            mint.as_ref(),
+        ];
+        let (metadata, _) = Pubkey::find_program_address(seeds, &TOKEN_METADATA_PROGRAM_ID);
+        let (edition, _) = Pubkey::find_program_address(
+            &[seeds, &[b"edition"]].concat(),
+            &TOKEN_METADATA_PROGRAM_ID,
+        );
+        (metadata, edition)
+    }
+
+    #[test]
+    fn test_validate_collection_accounts() {
+        let mint = Pubkey::new_unique();
+        let (metadata, edition) = collection_pdas(&mint);
+
+        let result =
+            validate_collection_accounts(&mint, &metadata.to_string(), &edition.to_string());
+        assert_eq!(result.unwrap(), (metadata, edition));
+
+        // Metadata and edition swapped
+        let result =
+            validate_collection_accounts(&mint, &edition.to_string(), &metadata.to_string());
+        assert!(matches!(result, Err(NifError::InvalidPubkey(_))));
+    }
+
+    /// A Token Metadata metadata account for `mint` with the given update authority
+    fn collection_metadata_account(mint: &Pubkey, update_authority: &Pubkey) -> Account {
+        let mut data = vec![0u8; 679];
+        data[0] = METADATA_V1_KEY;
+        data[1..33].copy_from_slice(update_authority.as_ref());
+        data[33..65].copy_from_slice(mint.as_ref());
+        Account {
+            lamports: 5_616_720,
+            data,
+            owner: TOKEN_METADATA_PROGRAM_ID,
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    #[test]
+    fn test_ensure_collection_authority() {
+        let mint = Pubkey::new_unique();
+        let (metadata, _) = collection_pdas(&mint);
+        let update_authority = Pubkey::new_unique();
+        let mut backend = MockRpc::default();
+        backend.accounts.insert(
+            metadata,
+            collection_metadata_account(&mint, &update_authority),
+        );
+
+        let result =
+            ensure_collection_authority(&backend, &mint, &metadata, &update_authority, false);
+        assert_eq!(result.unwrap(), None);
+
+        let stranger = Pubkey::new_unique();
+        let result = ensure_collection_authority(&backend, &mint, &metadata, &stranger, false);
+        assert!(matches!(result, Err(NifError::InvalidMetadata(_))));
+
+        // A collection authority record makes the stranger a delegate
+        let (record, _) = Pubkey::find_program_address(
+            &[
+                b"metadata",
+                TOKEN_METADATA_PROGRAM_ID.as_ref(),
+                mint.as_ref(),
+                b"collection_authority",
+                stranger.as_ref(),
+            ],
+            &TOKEN_METADATA_PROGRAM_ID,
+        );
+        backend.accounts.insert(
+            record,
+            Account::new(1_000_000, 35, &TOKEN_METADATA_PROGRAM_ID),
+        );
+        let result = ensure_collection_authority(&backend, &mint, &metadata, &stranger, false);
+        assert_eq!(result.unwrap(), Some(record));
+
+        // Metadata of another mint
+        let result = ensure_collection_authority(
+            &backend,
+            &Pubkey::new_unique(),
+            &metadata,
+            &update_authority,
+            false,
+        );
+        assert!(matches!(result, Err(NifError::InvalidMetadata(_))));
+    }
+
+    #[test]
+    fn test_ensure_collection_authority_collection_delegate() {
+        let mint = Pubkey::new_unique();
+        let (metadata, _) = collection_pdas(&mint);
+        let update_authority = Pubkey::new_unique();
+        let delegate = Pubkey::new_unique();
+        let mut backend = MockRpc::default();
+        backend.accounts.insert(
+            metadata,
+            collection_metadata_account(&mint, &update_authority),
+        );
+        let (record, _) = Pubkey::find_program_address(
+            &[
+                b"metadata",
+                TOKEN_METADATA_PROGRAM_ID.as_ref(),
+                mint.as_ref(),
+                b"collection_delegate",
+                update_authority.as_ref(),
+                delegate.as_ref(),
+            ],
+            &TOKEN_METADATA_PROGRAM_ID,
+        );
+        backend.accounts.insert(
+            record,
+            Account::new(1_000_000, 98, &TOKEN_METADATA_PROGRAM_ID),
+        );
+
+        // Bubblegum takes the delegate record, SetCollectionSize doesn't
+        let result = ensure_collection_authority(&backend, &mint, &metadata, &delegate, true);
+        assert_eq!(result.unwrap(), Some(record));
+        let result = ensure_collection_authority(&backend, &mint, &metadata, &delegate, false);
+        assert!(matches!(result, Err(NifError::InvalidMetadata(_))));
+    }
+
+    #[test]
+    fn test_build_set_collection_size_instruction() {
+        let mint = Pubkey::new_unique();
+        let (metadata, _) = collection_pdas(&mint);
+        let authority = Pubkey::new_unique();
+        let record = Pubkey::new_unique();
+
+        let instruction =
+            build_set_collection_size_instruction(&metadata, &authority, &mint, None, 1_000);
+        assert_eq!(instruction.program_id, TOKEN_METADATA_PROGRAM_ID);
+        assert_eq!(instruction.data[0], SET_COLLECTION_SIZE_DISCRIMINATOR);
+        assert_eq!(instruction.data[1..], 1_000u64.to_le_bytes());
+        assert_eq!(instruction.accounts.len(), 3);
+        assert!(instruction.accounts[0].is_writable);
+        assert!(instruction.accounts[1].is_signer);
+
+        let instruction = build_set_collection_size_instruction(
+            &metadata,
+            &authority,
+            &mint,
+            Some(record),
+            1_000,
+        );
+        assert_eq!(instruction.accounts[3].pubkey, record);
+    }
+
+    #[test]
+    fn test_set_and_verify_collection_wrong_collection_metadata() {
+        let authority = Keypair::new();
+        let mint = Pubkey::new_unique();
+        let (_, edition) = collection_pdas(&mint);
+
+        let result = set_and_verify_collection(
+            &RpcConfig::new(RPC_URL),
+            &Pubkey::new_unique().to_string(),
+            "",
+            &mint.to_string(),
+            &Pubkey::new_unique().to_string(),
+            &edition.to_string(),
+            &authority.to_base58_string(),
+            0,
+            true,
+            false,
+        );
+
+        assert!(
+            matches!(result, Err(NifError::InvalidPubkey(_))),
+            "Should reject a metadata account that isn't the collection's PDA"
+        );
+    }
+
+    #[test]
+    fn test_transfer_by_asset_id_with_nonce_invalid_nonce_account() {
+        let leaf_owner = Keypair::new();
+        let new_leaf_owner = Keypair::new();
+
+        let result = transfer_by_asset_id_with_nonce(
+            &RpcConfig::new(RPC_URL),
+            &Keypair::new().pubkey().to_string(),
+            &new_leaf_owner.pubkey().to_string(),
+            &leaf_owner.to_base58_string(),
+            "invalid_nonce_account",
+            &leaf_owner.to_base58_string(),
+            0,
+            true,
+            None,
+        );
+
+        assert!(result.is_err(), "Should fail with invalid nonce account");
+        if let Err(NifError::InvalidPubkey(_)) = result {
+            // Success
+        } else {
+            panic!("Wrong error type");
+        }
+    }
+
+    #[test]
+    fn test_submit_with_signer_resubmits_after_blockhash_expiry() {
+        let signer = Keypair::new();
+        let mut config = RpcConfig::new(RPC_URL);
+        config.confirm_timeout = Duration::ZERO;
+        let instructions = vec![Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[],
+            vec![],
+        )];
+
+        // Still valid: the transaction may land, so it is not sent again
+        let backend = MockRpc {
+            confirms: false,
+            ..MockRpc::default()
+        };
+        let result = submit_with_signer(
+            &config,
+            &backend,
+            instructions.clone(),
+            &signer,
+            &[],
+            true,
+            false,
+        );
+        assert!(matches!(result, Err(NifError::Timeout(_))));
+        assert_eq!(backend.sent.borrow().len(), 1);
+
+        let backend = MockRpc {
+            confirms: false,
+            blockhash_valid: false,
+            ..MockRpc::default()
+        };
+        let result = submit_with_signer(&config, &backend, instructions, &signer, &[], true, false);
+        assert!(matches!(result, Err(NifError::Timeout(_))));
+
+        let sent = backend.sent.borrow();
+        assert_eq!(sent.len(), config.max_resubmits as usize + 1);
+        // Resubmits carry a compute unit price ahead of the original instruction
+        assert_eq!(sent[0].message.instructions().len(), 1);
+        assert_eq!(sent[1].message.instructions().len(), 2);
+    }
+
+    #[test]
+    fn test_batch_mint_v1_continues_past_a_bad_item() {
+        let payer = Keypair::new();
+        let metadata_borsh =
+            serialize_metadata_to_borsh(&create_valid_metadata_json(&payer.pubkey().to_string()))
+                .unwrap();
+        let owner = Pubkey::new_unique().to_string();
+        let item = |leaf_owner: &str| {
+            (
+                leaf_owner.to_string(),
+                owner.clone(),
+                metadata_borsh.clone(),
+            )
+        };
+        let items = vec![item(&owner), item("not_a_pubkey"), item(&owner)];
+        let backend = MockRpc::default();
+
+        let results = batch_mint_with_client(
+            &RpcConfig::new(RPC_URL),
+            &backend,
+            Pubkey::new_unique(),
+            &items,
+            &payer,
+            true,
+            false,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok());
+        assert!(matches!(results[1], Err(NifError::InvalidPubkey(_))));
+        assert!(results[2].is_ok());
+        // Only the good items are sent, both signed with the batch's blockhash
+        let sent = backend.sent.borrow();
+        assert_eq!(sent.len(), 2);
+        assert!(sent
+            .iter()
+            .all(|tx| *tx.message.recent_blockhash() == backend.blockhash));
+    }
+
+    #[test]
+    fn test_finish_mint_remembers_timed_out_signature() {
+        let mut config = RpcConfig::new(RPC_URL);
+        config.idempotency_key = Some("test_finish_mint_remembers_timed_out_signature".to_string());
+        let asset_id = Pubkey::new_unique();
+        let signature = Signature::new_unique();
+
+        // A send that never went out leaves nothing to return on a retry
+        let result = finish_mint(
+            &config,
+            asset_id,
+            Err(NifError::RpcError("connection refused".to_string())),
+            None,
+        );
+        assert!(matches!(result, Err(NifError::RpcError(_))));
+        assert!(cached_mint(&config).is_none());
+
+        let result = finish_mint(
+            &config,
+            asset_id,
+            Err(NifError::Timeout("not confirmed".to_string())),
+            Some(signature),
+        );
+        assert!(matches!(result, Err(NifError::Timeout(_))));
+
+        let retried = cached_mint(&config).expect("timed-out mint should be remembered");
+        assert_eq!(retried.asset_id, asset_id.to_string());
+        assert_eq!(retried.signature, signature.to_string());
+        assert_eq!(retried.confirmation_status, None);
+    }
 }
@@ -0,0 +1,529 @@
+use mpl_bubblegum::{accounts::TreeConfig, programs::SPL_ACCOUNT_COMPRESSION_ID};
+use rustler::NifMap;
+use solana_sdk::{account::Account, pubkey::Pubkey};
+
+use crate::{
+    backend::RpcBackend,
+    error::NifError,
+    utils::{classify_client_error, new_rpc_client, parse_pubkey, rpc_timeout},
+};
+
+/// Decoded Bubblegum `TreeConfig` account, returned to Elixir as a map
+#[derive(NifMap, Debug, Clone)]
+pub struct TreeConfigInfo {
+    pub tree_creator: String,
+    pub tree_delegate: String,
+    pub total_mint_capacity: u64,
+    pub num_minted: u64,
+    pub is_public: bool,
+}
+
+impl From<TreeConfig> for TreeConfigInfo {
+    fn from(tree_config: TreeConfig) -> Self {
+        TreeConfigInfo {
+            tree_creator: tree_config.tree_creator.to_string(),
+            tree_delegate: tree_config.tree_delegate.to_string(),
+            total_mint_capacity: tree_config.total_mint_capacity,
+            num_minted: tree_config.num_minted,
+            is_public: tree_config.is_public,
+        }
+    }
+}
+
+/// Fetches and decodes the `TreeConfig` account at `tree_config_pubkey`
+pub fn get_tree_config(
+    rpc_url: &str,
+    tree_config_pubkey: &str,
+) -> Result<TreeConfigInfo, NifError> {
+    let tree_config = parse_pubkey(tree_config_pubkey)?;
+    let client = new_rpc_client(rpc_url, rpc_timeout(None));
+    fetch_tree_config(&client, &tree_config).map(TreeConfigInfo::from)
+}
+
+/// Returns how many more leaves can be minted into the tree behind `tree_config_pubkey`.
+///
+/// A missing tree config account is reported as `AccountNotFound` rather than an RPC error.
+pub fn tree_remaining_capacity(rpc_url: &str, tree_config_pubkey: &str) -> Result<u64, NifError> {
+    let tree_config = parse_pubkey(tree_config_pubkey)?;
+    let client = new_rpc_client(rpc_url, rpc_timeout(None));
+    let account = client
+        .get_account_with_commitment(&tree_config, client.commitment())
+        .map_err(|e| NifError::RpcError(e.to_string()))?
+        .value
+        .ok_or_else(|| NifError::AccountNotFound(format!("tree config {}", tree_config)))?;
+
+    decode_tree_config(&account.data).map(|tree_config| remaining_capacity(&tree_config))
+}
+
+/// Anchor discriminator of `TreeConfig` accounts: `sha256("account:TreeConfig")[..8]`
+const TREE_CONFIG_DISCRIMINATOR: [u8; 8] = [122, 245, 175, 248, 171, 34, 0, 207];
+
+/// Whether `tree_config_pubkey` holds an initialized Bubblegum `TreeConfig`.
+///
+/// A missing account, or one that isn't a tree config, is `false`; only RPC failures
+/// are errors.
+pub fn is_tree_initialized(rpc_url: &str, tree_config_pubkey: &str) -> Result<bool, NifError> {
+    let tree_config = parse_pubkey(tree_config_pubkey)?;
+    let client = new_rpc_client(rpc_url, rpc_timeout(None));
+    let account = client
+        .get_account_with_commitment(&tree_config, client.commitment())
+        .map_err(|e| NifError::RpcError(e.to_string()))?
+        .value;
+
+    Ok(account.is_some_and(|account| is_tree_config_account(&account)))
+}
+
+/// Whether an account is owned by Bubblegum and decodes as a `TreeConfig`
+fn is_tree_config_account(account: &Account) -> bool {
+    account.owner == mpl_bubblegum::ID
+        && decode_tree_config(&account.data)
+            .is_ok_and(|tree_config| tree_config.discriminator == TREE_CONFIG_DISCRIMINATOR)
+}
+
+/// Checks that `merkle_tree` is an account compression tree before anything is minted
+/// into it, so a wallet or other wrong account fails with a clear error instead of an
+/// opaque program failure
+pub fn ensure_merkle_tree_account(
+    client: &dyn RpcBackend,
+    merkle_tree: &Pubkey,
+) -> Result<(), NifError> {
+    let account = client
+        .get_optional_account(merkle_tree)
+        .map_err(classify_client_error)?;
+
+    if !account.is_some_and(|account| is_merkle_tree_account(&account)) {
+        return Err(NifError::InvalidMetadata(
+            "not a merkle tree account".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Whether an account is owned by the SPL account compression program
+fn is_merkle_tree_account(account: &Account) -> bool {
+    account.owner == SPL_ACCOUNT_COMPRESSION_ID
+}
+
+/// Leaves still available in a tree, never underflowing
+pub fn remaining_capacity(tree_config: &TreeConfig) -> u64 {
+    tree_config
+        .total_mint_capacity
+        .saturating_sub(tree_config.num_minted)
+}
+
+/// Helper to fetch and decode a `TreeConfig` account using an existing RPC client
+pub fn fetch_tree_config(
+    client: &dyn RpcBackend,
+    tree_config: &Pubkey,
+) -> Result<TreeConfig, NifError> {
+    let data = client
+        .get_account_data(tree_config)
+        .map_err(|e| NifError::RpcError(e.to_string()))?;
+    decode_tree_config(&data)
+}
+
+/// Size of the account compression header in front of a merkle tree: account type,
+/// header version, max buffer size, max depth, authority, creation slot and padding
+const MERKLE_TREE_HEADER_SIZE: usize = 56;
+
+/// `CompressionAccountType::ConcurrentMerkleTree`
+const MERKLE_TREE_ACCOUNT_TYPE: u8 = 1;
+
+/// Returns the canopy depth of the merkle tree at `merkle_tree_pubkey`, read from the
+/// size of the account.
+///
+/// Pass it as `canopy_depth` so proofs are trimmed by exactly the nodes the tree
+/// caches on-chain.
+pub fn get_tree_canopy_depth(rpc_url: &str, merkle_tree_pubkey: &str) -> Result<u32, NifError> {
+    let merkle_tree = parse_pubkey(merkle_tree_pubkey)?;
+    let client = new_rpc_client(rpc_url, rpc_timeout(None));
+    let account = client
+        .get_account_with_commitment(&merkle_tree, client.commitment())
+        .map_err(|e| NifError::RpcError(e.to_string()))?
+        .value
+        .ok_or_else(|| NifError::AccountNotFound(format!("merkle tree {}", merkle_tree)))?;
+
+    canopy_depth_from_account_data(&account.data)
+}
+
+/// Computes the canopy depth from raw merkle tree account data.
+///
+/// The header gives the max depth and buffer size, and with them the size of the tree
+/// itself. Whatever follows is the canopy, which caches the `2^(d + 1) - 2` nodes of
+/// the top `d` levels.
+pub fn canopy_depth_from_account_data(data: &[u8]) -> Result<u32, NifError> {
+    let layout = MerkleTreeLayout::from_account_data(data)?;
+    let canopy_size = data.len() - MERKLE_TREE_HEADER_SIZE - layout.tree_size();
+
+    let canopy_nodes = canopy_size / 32 + 2;
+    if !canopy_size.is_multiple_of(32) || !canopy_nodes.is_power_of_two() {
+        return Err(invalid_merkle_tree(
+            "canopy is not a whole number of levels",
+        ));
+    }
+    Ok(canopy_nodes.trailing_zeros() - 1)
+}
+
+/// Checks that `root` is one the tree still accepts proofs against.
+///
+/// The tree keeps its last `max_buffer_size` roots and fast-forwards proofs made
+/// against any of them, so only a root that has dropped out of that buffer is stale.
+/// A stale proof is reported before anything is submitted; fetching a fresh proof
+/// and retrying fixes it.
+pub fn ensure_root_is_current(
+    client: &dyn RpcBackend,
+    merkle_tree: &Pubkey,
+    root: &[u8; 32],
+) -> Result<(), NifError> {
+    let data = fetch_merkle_tree_data(client, merkle_tree)?;
+    if !recent_roots_from_account_data(&data)?.contains(root) {
+        return Err(NifError::InvalidMetadata("proof root is stale".to_string()));
+    }
+    Ok(())
+}
+
+/// Fetches the raw account data of the merkle tree at `merkle_tree`
+pub fn fetch_merkle_tree_data(
+    client: &dyn RpcBackend,
+    merkle_tree: &Pubkey,
+) -> Result<Vec<u8>, NifError> {
+    client
+        .get_optional_account(merkle_tree)
+        .map_err(classify_client_error)?
+        .map(|account| account.data)
+        .ok_or_else(|| NifError::AccountNotFound(format!("merkle tree {}", merkle_tree)))
+}
+
+/// The last leaf appended to a tree and its proof, which the tree keeps up to date
+#[derive(Debug, Clone, PartialEq)]
+pub struct RightmostLeaf {
+    pub proof: Vec<[u8; 32]>,
+    pub leaf: [u8; 32],
+    /// Index the next appended leaf will get, i.e. one past this leaf's
+    pub next_index: u32,
+}
+
+/// Reads the rightmost proof stored after a merkle tree's change log buffer
+pub fn rightmost_leaf_from_account_data(data: &[u8]) -> Result<RightmostLeaf, NifError> {
+    let layout = MerkleTreeLayout::from_account_data(data)?;
+    let offset = MERKLE_TREE_HEADER_SIZE + 24 + layout.max_buffer_size * layout.change_log_size();
+    let node = |index: usize| -> [u8; 32] {
+        let start = offset + index * 32;
+        data[start..start + 32].try_into().unwrap()
+    };
+    let index_offset = offset + 32 * (layout.max_depth + 1);
+    Ok(RightmostLeaf {
+        proof: (0..layout.max_depth).map(node).collect(),
+        leaf: node(layout.max_depth),
+        next_index: u32::from_le_bytes(data[index_offset..index_offset + 4].try_into().unwrap()),
+    })
+}
+
+/// Roots held in a merkle tree's change log buffer, newest first
+pub fn recent_roots_from_account_data(data: &[u8]) -> Result<Vec<[u8; 32]>, NifError> {
+    let layout = MerkleTreeLayout::from_account_data(data)?;
+    let tree = &data[MERKLE_TREE_HEADER_SIZE..];
+    let read_u64 = |offset: usize| {
+        let bytes: [u8; 8] = tree[offset..offset + 8].try_into().unwrap();
+        u64::from_le_bytes(bytes) as usize
+    };
+    let active_index = read_u64(8);
+    let buffer_size = read_u64(16);
+    if layout.max_buffer_size == 0
+        || active_index >= layout.max_buffer_size
+        || buffer_size > layout.max_buffer_size
+    {
+        return Err(invalid_merkle_tree("change log buffer is out of range"));
+    }
+
+    Ok((0..buffer_size)
+        .map(|age| {
+            let index = (active_index + layout.max_buffer_size - age) % layout.max_buffer_size;
+            let offset = 24 + index * layout.change_log_size();
+            tree[offset..offset + 32].try_into().unwrap()
+        })
+        .collect())
+}
+
+/// Dimensions of a concurrent merkle tree, read from its account header
+struct MerkleTreeLayout {
+    max_buffer_size: usize,
+    max_depth: usize,
+}
+
+impl MerkleTreeLayout {
+    /// Reads the header, checking the account is big enough for the tree it describes
+    fn from_account_data(data: &[u8]) -> Result<Self, NifError> {
+        if data.len() < MERKLE_TREE_HEADER_SIZE || data[0] != MERKLE_TREE_ACCOUNT_TYPE {
+            return Err(invalid_merkle_tree("not a concurrent merkle tree"));
+        }
+        let read_u32 = |offset: usize| {
+            let bytes: [u8; 4] = data[offset..offset + 4].try_into().unwrap();
+            u32::from_le_bytes(bytes) as usize
+        };
+        let layout = MerkleTreeLayout {
+            max_buffer_size: read_u32(2),
+            max_depth: read_u32(6),
+        };
+        if data.len() < MERKLE_TREE_HEADER_SIZE + layout.tree_size() {
+            return Err(invalid_merkle_tree(
+                "account is smaller than its header says",
+            ));
+        }
+        Ok(layout)
+    }
+
+    /// A change log: root, path, index and padding
+    fn change_log_size(&self) -> usize {
+        32 + 32 * self.max_depth + 8
+    }
+
+    /// Sequence number, active index and buffer size, then the change logs and the
+    /// rightmost proof (path, leaf, index and padding)
+    fn tree_size(&self) -> usize {
+        24 + self.max_buffer_size * self.change_log_size() + 32 * self.max_depth + 40
+    }
+}
+
+fn invalid_merkle_tree(reason: &str) -> NifError {
+    NifError::SerializationError(format!("invalid merkle tree account: {}", reason))
+}
+
+/// Helper to deserialize raw account data into a `TreeConfig`
+pub fn decode_tree_config(data: &[u8]) -> Result<TreeConfig, NifError> {
+    TreeConfig::from_bytes(data)
+        .map_err(|e| NifError::SerializationError(format!("TreeConfig deserialize error: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::MockRpc;
+    use borsh::BorshSerialize;
+    use mpl_bubblegum::types::DecompressibleState;
+
+    fn sample_tree_config() -> TreeConfig {
+        TreeConfig {
+            discriminator: TREE_CONFIG_DISCRIMINATOR,
+            tree_creator: Pubkey::new_unique(),
+            tree_delegate: Pubkey::new_unique(),
+            total_mint_capacity: 16_384,
+            num_minted: 42,
+            is_public: false,
+            is_decompressible: DecompressibleState::Disabled,
+        }
+    }
+
+    /// Merkle tree account data as account compression lays it out
+    fn merkle_tree_account(max_depth: usize, max_buffer_size: usize, canopy_depth: u32) -> Vec<u8> {
+        let mut data = vec![0u8; MERKLE_TREE_HEADER_SIZE];
+        data[0] = MERKLE_TREE_ACCOUNT_TYPE;
+        data[2..6].copy_from_slice(&(max_buffer_size as u32).to_le_bytes());
+        data[6..10].copy_from_slice(&(max_depth as u32).to_le_bytes());
+
+        let tree_size = 24 + max_buffer_size * (40 + 32 * max_depth) + 32 * max_depth + 40;
+        let canopy_size = ((1 << (canopy_depth + 1)) - 2) * 32;
+        data.resize(data.len() + tree_size + canopy_size, 0);
+        data
+    }
+
+    #[test]
+    fn test_canopy_depth_from_account_data() {
+        for (max_depth, max_buffer_size, canopy_depth) in [(14, 64, 0), (14, 64, 10), (20, 256, 17)]
+        {
+            let data = merkle_tree_account(max_depth, max_buffer_size, canopy_depth);
+            assert_eq!(canopy_depth_from_account_data(&data).unwrap(), canopy_depth);
+        }
+    }
+
+    #[test]
+    fn test_recent_roots_from_account_data() {
+        let (max_depth, max_buffer_size) = (3, 4);
+        let mut data = merkle_tree_account(max_depth, max_buffer_size, 0);
+        let change_log_size = 40 + 32 * max_depth;
+        let tree = MERKLE_TREE_HEADER_SIZE;
+        // Five updates into a four-entry buffer: the oldest root has been overwritten
+        data[tree + 8..tree + 16].copy_from_slice(&1u64.to_le_bytes());
+        data[tree + 16..tree + 24].copy_from_slice(&4u64.to_le_bytes());
+        for (index, root) in [[5u8; 32], [6; 32], [3; 32], [4; 32]].iter().enumerate() {
+            let offset = tree + 24 + index * change_log_size;
+            data[offset..offset + 32].copy_from_slice(root);
+        }
+
+        let roots = recent_roots_from_account_data(&data).unwrap();
+        assert_eq!(roots, vec![[6; 32], [5; 32], [4; 32], [3; 32]]);
+
+        // An active index past the buffer is corrupt data, not a panic
+        data[tree + 8..tree + 16].copy_from_slice(&4u64.to_le_bytes());
+        assert!(matches!(
+            recent_roots_from_account_data(&data),
+            Err(NifError::SerializationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_rightmost_leaf_from_account_data() {
+        let (max_depth, max_buffer_size) = (3, 4);
+        let mut data = merkle_tree_account(max_depth, max_buffer_size, 2);
+        let offset = MERKLE_TREE_HEADER_SIZE + 24 + max_buffer_size * (40 + 32 * max_depth);
+        for (index, node) in [[1u8; 32], [2; 32], [3; 32], [9; 32]].iter().enumerate() {
+            data[offset + index * 32..offset + (index + 1) * 32].copy_from_slice(node);
+        }
+        data[offset + 128..offset + 132].copy_from_slice(&5u32.to_le_bytes());
+
+        let rightmost = rightmost_leaf_from_account_data(&data).unwrap();
+        assert_eq!(
+            rightmost,
+            RightmostLeaf {
+                proof: vec![[1; 32], [2; 32], [3; 32]],
+                leaf: [9; 32],
+                next_index: 5,
+            }
+        );
+    }
+
+    #[test]
+    fn test_canopy_depth_rejects_bad_data() {
+        let mut wrong_type = merkle_tree_account(14, 64, 3);
+        wrong_type[0] = 0;
+        let mut partial_level = merkle_tree_account(14, 64, 3);
+        partial_level.extend_from_slice(&[0; 32]);
+        let truncated = merkle_tree_account(14, 64, 0)[..100].to_vec();
+
+        for data in [vec![], wrong_type, partial_level, truncated] {
+            assert!(matches!(
+                canopy_depth_from_account_data(&data),
+                Err(NifError::SerializationError(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_is_merkle_tree_account() {
+        let account = |owner| Account {
+            owner,
+            ..Account::default()
+        };
+        assert!(is_merkle_tree_account(&account(SPL_ACCOUNT_COMPRESSION_ID)));
+        assert!(!is_merkle_tree_account(&account(
+            solana_sdk::system_program::ID
+        )));
+        assert!(!is_merkle_tree_account(&account(mpl_bubblegum::ID)));
+    }
+
+    #[test]
+    fn test_ensure_merkle_tree_account() {
+        let tree = Pubkey::new_unique();
+        let wallet = Pubkey::new_unique();
+        let mut backend = MockRpc::default();
+        backend.accounts.insert(
+            tree,
+            Account {
+                owner: SPL_ACCOUNT_COMPRESSION_ID,
+                ..Account::default()
+            },
+        );
+        backend.accounts.insert(wallet, Account::default());
+
+        assert!(ensure_merkle_tree_account(&backend, &tree).is_ok());
+        for merkle_tree in [wallet, Pubkey::new_unique()] {
+            assert!(matches!(
+                ensure_merkle_tree_account(&backend, &merkle_tree),
+                Err(NifError::InvalidMetadata(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_decode_tree_config_valid() {
+        let tree_config = sample_tree_config();
+        let data = tree_config.try_to_vec().unwrap();
+
+        let result = decode_tree_config(&data);
+        assert!(
+            result.is_ok(),
+            "Failed to decode tree config: {:?}",
+            result.err()
+        );
+        let info = TreeConfigInfo::from(result.unwrap());
+        assert_eq!(info.num_minted, 42);
+        assert_eq!(info.total_mint_capacity, 16_384);
+        assert!(!info.is_public);
+        assert_eq!(info.tree_delegate, tree_config.tree_delegate.to_string());
+    }
+
+    #[test]
+    fn test_decode_tree_config_truncated() {
+        let data = sample_tree_config().try_to_vec().unwrap();
+
+        let result = decode_tree_config(&data[..20]);
+        assert!(result.is_err(), "Should fail with truncated data");
+        if let Err(NifError::SerializationError(_)) = result {
+            // Success
+        } else {
+            panic!("Wrong error type");
+        }
+    }
+
+    #[test]
+    fn test_remaining_capacity() {
+        let mut tree_config = sample_tree_config();
+        assert_eq!(remaining_capacity(&tree_config), 16_384 - 42);
+
+        tree_config.num_minted = tree_config.total_mint_capacity + 1;
+        assert_eq!(remaining_capacity(&tree_config), 0);
+    }
+
+    #[test]
+    fn test_is_tree_config_account() {
+        let data = sample_tree_config().try_to_vec().unwrap();
+        let mut account = Account {
+            lamports: 1_000_000,
+            data,
+            owner: mpl_bubblegum::ID,
+            executable: false,
+            rent_epoch: 0,
+        };
+        assert!(is_tree_config_account(&account));
+
+        account.data[0] ^= 0xff;
+        assert!(!is_tree_config_account(&account), "Wrong discriminator");
+
+        account.data[0] ^= 0xff;
+        account.owner = Pubkey::new_unique();
+        assert!(!is_tree_config_account(&account), "Wrong owner");
+    }
+
+    #[test]
+    fn test_is_tree_initialized_missing_account() {
+        let missing = Pubkey::new_unique().to_string();
+        let result = is_tree_initialized("https://api.devnet.solana.com", &missing);
+        assert!(
+            !result.unwrap(),
+            "A missing account is not an initialized tree"
+        );
+    }
+
+    #[test]
+    fn test_tree_remaining_capacity_missing_account() {
+        let missing = Pubkey::new_unique().to_string();
+        let result = tree_remaining_capacity("https://api.devnet.solana.com", &missing);
+        assert!(result.is_err(), "Should fail with a missing tree config");
+        if let Err(NifError::AccountNotFound(_)) = result {
+            // Success
+        } else {
+            panic!("Wrong error type: {:?}", result);
+        }
+    }
+
+    #[test]
+    fn test_get_tree_config_invalid_pubkey() {
+        let result = get_tree_config("https://api.devnet.solana.com", "invalid_pubkey");
+        assert!(result.is_err(), "Should fail with invalid pubkey");
+        if let Err(NifError::InvalidPubkey(_)) = result {
+            // Success
+        } else {
+            panic!("Wrong error type");
+        }
+    }
+}